@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
+use crate::game::LevelCatalog;
+
 #[derive(AssetCollection, Resource)]
-pub struct CoreAssets {}
+pub struct CoreAssets {
+    #[asset(key = "levels")]
+    pub levels: Handle<LevelCatalog>,
+}
@@ -3,11 +3,26 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_asset_loader::prelude::*;
 
+use crate::game::{PhysicsMaterialTable, SoundBank, TowerCatalog, WavesDefinition};
+
 #[derive(AssetCollection, Resource)]
 pub struct GameAssets {
+    // "world" & "waves" are registered from the level catalog, for the current level
     #[asset(key = "world")]
     pub world: Handle<Gltf>,
 
     #[asset(key = "models", collection(typed, mapped))]
     pub models: HashMap<String, Handle<Gltf>>,
+
+    #[asset(key = "waves")]
+    pub waves: Handle<WavesDefinition>,
+
+    #[asset(key = "towers")]
+    pub towers: Handle<TowerCatalog>,
+
+    #[asset(key = "materials")]
+    pub materials: Handle<PhysicsMaterialTable>,
+
+    #[asset(key = "sounds")]
+    pub sounds: Handle<SoundBank>,
 }
@@ -0,0 +1,203 @@
+use bevy::app::AppExit;
+use bevy::asset::{LoadState, RecursiveDependencyLoadState};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::*;
+use iyes_progress::prelude::*;
+
+use crate::game::{menu_root, menu_text, menu_title};
+use crate::state::{AppState, StateScoped};
+
+use super::CoreAssets;
+
+// keys of the dynamic assets loaded in each loading state, in the order they are shown as pending
+const CORE_ASSET_KEYS: [&str; 1] = ["levels"];
+const GAME_ASSET_KEYS: [&str; 6] = ["world", "models", "waves", "towers", "materials", "sounds"];
+
+const BAR_WIDTH: f32 = 400.0;
+
+/// What went wrong, shown by the error screen
+#[derive(Resource, Debug, Default)]
+pub struct LoadingError(pub String);
+
+/// Handles of the dynamic assets of the current loading state, by key, to poll their load state
+#[derive(Resource, Debug, Default)]
+pub struct LoadingHandles(HashMap<&'static str, Vec<UntypedHandle>>);
+
+#[derive(Component)]
+pub struct LoadingProgressBar;
+
+#[derive(Component)]
+pub struct LoadingPendingText;
+
+fn loading_screen_root() -> NodeBundle {
+    NodeBundle {
+        background_color: Color::BLACK.into(),
+        ..menu_root()
+    }
+}
+
+pub fn setup_loading_screen(mut commands: Commands, state: Res<State<AppState>>) {
+    commands.insert_resource(LoadingHandles::default());
+    commands
+        .spawn((loading_screen_root(), StateScoped(*state.get())))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Loading..."));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(BAR_WIDTH),
+                        height: Val::Px(20.0),
+                        ..default()
+                    },
+                    background_color: Color::rgb(0.15, 0.15, 0.15).into(),
+                    ..default()
+                })
+                .with_children(|bar| {
+                    bar.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::rgb(0.35, 0.35, 0.55).into(),
+                            ..default()
+                        },
+                        LoadingProgressBar,
+                    ));
+                });
+            parent.spawn((menu_text("", 16.0), LoadingPendingText));
+        });
+}
+
+// the handles must not outlive the loading state, or they would keep unloaded levels alive
+pub fn drop_loading_handles(mut commands: Commands) {
+    commands.remove_resource::<LoadingHandles>();
+}
+
+pub fn update_loading_screen(
+    state: Res<State<AppState>>,
+    progress: Option<Res<ProgressCounter>>,
+    dynamic_assets: Res<DynamicAssets>,
+    asset_server: Res<AssetServer>,
+    mut handles: ResMut<LoadingHandles>,
+    mut bars: Query<&mut Style, With<LoadingProgressBar>>,
+    mut texts: Query<&mut Text, With<LoadingPendingText>>,
+    mut commands: Commands,
+) {
+    if let Some(progress) = progress {
+        let Progress { done, total } = progress.progress();
+        let fraction = if total > 0 {
+            done as f32 / total as f32
+        } else {
+            0.0
+        };
+        for mut style in bars.iter_mut() {
+            style.width = Val::Percent(fraction * 100.0);
+        }
+    }
+
+    let keys: &[&str] = match state.get() {
+        AppState::CoreLoading => &CORE_ASSET_KEYS,
+        _ => &GAME_ASSET_KEYS,
+    };
+    // keys get registered once the dynamic collection file is loaded, each one is loaded as soon as it is there.
+    // The handles are the same as the loader's: the asset server does not load the same path twice
+    for key in keys {
+        if handles.0.contains_key(key) {
+            continue;
+        }
+        if let Some(asset) = dynamic_assets.get_asset(key) {
+            handles.0.insert(key, asset.load(&asset_server));
+        }
+    }
+
+    let mut pending = None;
+    for key in keys {
+        let Some(key_handles) = handles.0.get(key) else {
+            continue;
+        };
+        for handle in key_handles {
+            let path = asset_server
+                .get_path(handle.id())
+                .map_or(key.to_string(), |path| path.to_string());
+            if asset_server.get_load_state(handle.id()) == Some(LoadState::Failed)
+                || asset_server.get_recursive_dependency_load_state(handle.id())
+                    == Some(RecursiveDependencyLoadState::Failed)
+            {
+                commands.insert_resource(LoadingError(format!("could not load {}", path)));
+            } else if pending.is_none()
+                && asset_server.get_recursive_dependency_load_state(handle.id())
+                    != Some(RecursiveDependencyLoadState::Loaded)
+            {
+                pending = Some(path);
+            }
+        }
+    }
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = pending.clone().unwrap_or_default();
+    }
+}
+
+pub fn setup_loading_error_screen(
+    mut commands: Commands,
+    error: Option<Res<LoadingError>>,
+    core_assets: Option<Res<CoreAssets>>,
+) {
+    let message = error.map_or("an asset failed to load".to_string(), |error| {
+        error.0.clone()
+    });
+    error!("{}", message);
+    let hint = if core_assets.is_some() {
+        "press Enter to go back to the main menu, Escape to quit"
+    } else {
+        "press Escape to quit"
+    };
+    commands
+        .spawn((loading_screen_root(), StateScoped(AppState::LoadingFailed)))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Loading failed"));
+            parent.spawn(menu_text(message, 18.0));
+            parent.spawn(menu_text(hint, 18.0));
+        });
+}
+
+pub fn loading_error_screen(
+    keycode: Res<Input<KeyCode>>,
+    core_assets: Option<Res<CoreAssets>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut commands: Commands,
+) {
+    // the main menu needs the core assets
+    if keycode.just_pressed(KeyCode::Return) && core_assets.is_some() {
+        commands.remove_resource::<LoadingError>();
+        next_app_state.set(AppState::MenuRunning);
+    }
+    if keycode.just_pressed(KeyCode::Escape) {
+        app_exit_events.send(AppExit);
+    }
+}
+
+pub struct LoadingScreenPlugin;
+impl Plugin for LoadingScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::CoreLoading), setup_loading_screen)
+            .add_systems(OnEnter(AppState::AppLoading), setup_loading_screen)
+            .add_systems(OnExit(AppState::CoreLoading), drop_loading_handles)
+            .add_systems(OnExit(AppState::AppLoading), drop_loading_handles)
+            .add_systems(
+                Update,
+                update_loading_screen.run_if(
+                    in_state(AppState::CoreLoading).or_else(in_state(AppState::AppLoading)),
+                ),
+            )
+            .add_systems(OnEnter(AppState::LoadingFailed), setup_loading_error_screen)
+            .add_systems(
+                Update,
+                loading_error_screen.run_if(in_state(AppState::LoadingFailed)),
+            );
+    }
+}
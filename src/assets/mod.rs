@@ -4,18 +4,37 @@ pub use assets_core::*;
 pub mod assets_game;
 pub use assets_game::*;
 
+pub mod loading_screen;
+pub use loading_screen::*;
+
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use iyes_progress::prelude::*;
 
+use crate::game::{
+    LevelCatalog, PhysicsMaterialTable, SoundBank, TowerCatalog, WavesDefinition,
+};
 use crate::state::AppState;
 
 pub struct AssetsPlugin;
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.add_plugins((
+            RonAssetPlugin::<WavesDefinition>::new(&["waves.ron"]),
+            RonAssetPlugin::<TowerCatalog>::new(&["towers.ron"]),
+            RonAssetPlugin::<LevelCatalog>::new(&["levels.ron"]),
+            RonAssetPlugin::<PhysicsMaterialTable>::new(&["materials.ron"]),
+            RonAssetPlugin::<SoundBank>::new(&["sounds.ron"]),
+            // the loading states are left once everything reports as loaded
+            ProgressPlugin::new(AppState::CoreLoading).continue_to(AppState::MenuRunning),
+            ProgressPlugin::new(AppState::AppLoading).continue_to(AppState::AppRunning),
+            LoadingScreenPlugin,
+        ))
             // load core assets (ie assets needed in the main menu, and everywhere else before loading more assets in game)
             .add_loading_state(
-                LoadingState::new(AppState::CoreLoading).continue_to_state(AppState::MenuRunning),
+                LoadingState::new(AppState::CoreLoading)
+                    .on_failure_continue_to_state(AppState::LoadingFailed),
             )
             .add_dynamic_collection_to_loading_state::<_, StandardDynamicAssetCollection>(
                 AppState::CoreLoading,
@@ -24,7 +43,8 @@ impl Plugin for AssetsPlugin {
             .add_collection_to_loading_state::<_, CoreAssets>(AppState::CoreLoading)
             // load game assets
             .add_loading_state(
-                LoadingState::new(AppState::AppLoading).continue_to_state(AppState::AppRunning),
+                LoadingState::new(AppState::AppLoading)
+                    .on_failure_continue_to_state(AppState::LoadingFailed),
             )
             .add_dynamic_collection_to_loading_state::<_, StandardDynamicAssetCollection>(
                 AppState::AppLoading,
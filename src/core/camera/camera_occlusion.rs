@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_xpbd_3d::prelude::{Collider, Sensor, SpatialQuery, SpatialQueryFilter};
+use smooth_bevy_cameras::LookTransform;
+
+use crate::core::{find_in_ancestors, GameLayer};
+
+use super::{CameraMode, CameraRig, MainCamera};
+
+// how far the eye keeps from the geometry it is pulled in front of
+const CAMERA_RADIUS: f32 = 0.3;
+// the eye is never pulled closer than this to what it looks at
+const MIN_CAMERA_DISTANCE: f32 = 1.0;
+const MAX_CAMERA_HITS: u32 = 16;
+const FADED_OCCLUDER_ALPHA: f32 = 0.3;
+
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[reflect(Component, Default)]
+/// Add this to props (trees, rocks...) that should not push the camera around when they are between it & what it
+/// looks at, but get out of the way instead. They are found through their colliders, their own or their descendants'
+pub enum CameraOccluder {
+    /// made see-through
+    #[default]
+    Fade,
+    Hidden,
+}
+
+#[derive(Component, Debug)]
+/// On occluders while they are faded out or hidden
+pub struct Occluding;
+
+#[derive(Component, Debug)]
+/// On the meshes of a faded out occluder: their own material, given back once the occluder is out of the way
+pub struct FadedMaterial(Handle<StandardMaterial>);
+
+// from what the camera looks at to the eye, like a spring arm: the eye is pulled in front of the first obstacle,
+// occluders aside
+pub fn camera_collision(
+    spatial_query: SpatialQuery,
+    occluders: Query<(), With<CameraOccluder>>,
+    sensors: Query<(), With<Sensor>>,
+    parents: Query<&Parent>,
+    mut cameras: Query<(&mut LookTransform, &CameraRig), With<MainCamera>>,
+) {
+    let is_obstacle = |entity: Entity| {
+        !sensors.contains(entity)
+            && find_in_ancestors(entity, &parents, |entity| occluders.contains(entity)).is_none()
+    };
+    for (mut look_transform, rig) in cameras.iter_mut() {
+        // the overview is high enough above everything
+        if rig.mode == CameraMode::Overview {
+            continue;
+        }
+        let to_eye = look_transform.eye - look_transform.target;
+        let distance = to_eye.length();
+        if distance <= MIN_CAMERA_DISTANCE {
+            continue;
+        }
+        let direction = to_eye / distance;
+
+        // the terrain layer includes the colliders without layers, but not the player, enemies...
+        let closest = spatial_query
+            .shape_hits(
+                &Collider::ball(CAMERA_RADIUS),
+                look_transform.target,
+                Quat::IDENTITY,
+                direction,
+                distance,
+                MAX_CAMERA_HITS,
+                true,
+                SpatialQueryFilter::new().with_masks([GameLayer::Terrain]),
+            )
+            .into_iter()
+            .filter(|hit| is_obstacle(hit.entity))
+            .map(|hit| hit.time_of_impact)
+            .reduce(f32::min);
+        if let Some(time_of_impact) = closest {
+            look_transform.eye =
+                look_transform.target + direction * time_of_impact.max(MIN_CAMERA_DISTANCE);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn fade_camera_occluders(
+    spatial_query: SpatialQuery,
+    cameras: Query<&LookTransform, With<MainCamera>>,
+    occluders: Query<(Entity, &CameraOccluder)>,
+    occluding: Query<Entity, With<Occluding>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    mesh_materials: Query<&Handle<StandardMaterial>, Without<FadedMaterial>>,
+    faded_materials: Query<&FadedMaterial>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let mut in_the_way = HashSet::new();
+    for look_transform in cameras.iter() {
+        let to_eye = look_transform.eye - look_transform.target;
+        let distance = to_eye.length();
+        if distance <= f32::EPSILON {
+            continue;
+        }
+        for hit in spatial_query.ray_hits(
+            look_transform.target,
+            to_eye / distance,
+            distance,
+            MAX_CAMERA_HITS,
+            true,
+            SpatialQueryFilter::new(),
+        ) {
+            if let Some(occluder) =
+                find_in_ancestors(hit.entity, &parents, |entity| occluders.contains(entity))
+            {
+                in_the_way.insert(occluder);
+            }
+        }
+    }
+
+    for occluder in occluding.iter() {
+        if in_the_way.contains(&occluder) {
+            continue;
+        }
+        if let Ok((_, CameraOccluder::Hidden)) = occluders.get(occluder) {
+            commands.entity(occluder).insert(Visibility::Inherited);
+        }
+        for entity in std::iter::once(occluder).chain(children.iter_descendants(occluder)) {
+            if let Ok(FadedMaterial(material)) = faded_materials.get(entity) {
+                commands
+                    .entity(entity)
+                    .insert(material.clone())
+                    .remove::<FadedMaterial>();
+            }
+        }
+        commands.entity(occluder).remove::<Occluding>();
+    }
+
+    for occluder in in_the_way {
+        if occluding.contains(occluder) {
+            continue;
+        }
+        let Ok((_, mode)) = occluders.get(occluder) else {
+            continue;
+        };
+        match mode {
+            CameraOccluder::Hidden => {
+                commands.entity(occluder).insert(Visibility::Hidden);
+            }
+            CameraOccluder::Fade => {
+                // each mesh gets its own see-through copy, the original might be shared with other props
+                for entity in std::iter::once(occluder).chain(children.iter_descendants(occluder)) {
+                    let Ok(handle) = mesh_materials.get(entity) else {
+                        continue;
+                    };
+                    let Some(mut material) = materials.get(handle).cloned() else {
+                        continue;
+                    };
+                    material.base_color = material.base_color.with_a(FADED_OCCLUDER_ALPHA);
+                    material.alpha_mode = AlphaMode::Blend;
+                    commands
+                        .entity(entity)
+                        .insert((materials.add(material), FadedMaterial(handle.clone())));
+                }
+            }
+        }
+        commands.entity(occluder).insert(Occluding);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::scene::ScenePlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::transform::TransformPlugin;
+    use bevy_xpbd_3d::prelude::{PhysicsPlugins, RigidBody};
+
+    use super::*;
+
+    // a camera 10 units behind what it looks at, with a wall halfway & a tree closer still
+    fn occluded_camera_app(mode: CameraMode) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            // for the colliders xpbd can build from meshes & scenes
+            AssetPlugin::default(),
+            ScenePlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_asset::<Mesh>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+        app.world.spawn((
+            MainCamera,
+            CameraRig { mode, ..default() },
+            LookTransform::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO, Vec3::Y),
+        ));
+        app.world.spawn((
+            RigidBody::Static,
+            Collider::cuboid(4.0, 4.0, 0.2),
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, 5.0)),
+        ));
+        app.world
+            .spawn((
+                CameraOccluder::Fade,
+                RigidBody::Static,
+                SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, 2.5)),
+            ))
+            .with_children(|tree| {
+                tree.spawn((Collider::cuboid(1.0, 4.0, 1.0), TransformBundle::default()));
+            });
+        app.world.spawn((
+            RigidBody::Static,
+            Collider::cuboid(4.0, 4.0, 0.2),
+            Sensor,
+            TransformBundle::from_transform(Transform::from_xyz(0.0, 0.0, 1.5)),
+        ));
+        // lets the spatial query pipeline pick the colliders up
+        for _ in 0..3 {
+            app.update();
+        }
+        app.world.run_system_once(camera_collision);
+        app
+    }
+
+    fn eye(app: &mut App) -> Vec3 {
+        app.world.query::<&LookTransform>().single(&app.world).eye
+    }
+
+    #[test]
+    fn the_eye_is_pulled_in_front_of_walls_but_not_of_occluders() {
+        let mut app = occluded_camera_app(CameraMode::FollowPlayer);
+        // the front of the wall, minus the radius of the camera
+        let expected = 5.0 - 0.1 - CAMERA_RADIUS;
+        assert!((eye(&mut app).z - expected).abs() < 1e-3);
+        assert_eq!(eye(&mut app).x, 0.0);
+    }
+
+    #[test]
+    fn the_overview_is_left_alone() {
+        let mut app = occluded_camera_app(CameraMode::Overview);
+        assert_eq!(eye(&mut app), Vec3::new(0.0, 0.0, 10.0));
+    }
+}
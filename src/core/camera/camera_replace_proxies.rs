@@ -1,18 +1,54 @@
 use bevy::core_pipeline::bloom::{BloomCompositeMode, BloomSettings};
 use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
 use bevy::prelude::*;
+use smooth_bevy_cameras::{LookTransform, Smoother};
 
-use super::CameraTrackingOffset;
+use super::{CameraRig, CameraTrackingOffset, MainCamera};
 
+// cameras authored in a level are only stand-ins: the main camera takes over their rig, position & rendering settings
+#[allow(clippy::type_complexity)]
 pub fn camera_replace_proxies(
     mut commands: Commands,
-    mut added_cameras: Query<(Entity, &mut Camera), (Added<Camera>, With<CameraTrackingOffset>)>,
+    added_cameras: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&CameraRig>,
+            Option<&CameraTrackingOffset>,
+        ),
+        (
+            Added<Camera>,
+            Without<MainCamera>,
+            Or<(With<CameraRig>, With<CameraTrackingOffset>)>,
+        ),
+    >,
+    mut main_cameras: Query<
+        (Entity, &mut Camera, &mut LookTransform, &mut CameraRig),
+        With<MainCamera>,
+    >,
 ) {
-    for (entity, mut camera) in added_cameras.iter_mut() {
+    let Ok((main_camera, mut camera, mut look_transform, mut main_rig)) =
+        main_cameras.get_single_mut()
+    else {
+        return;
+    };
+    for (entity, transform, rig, tracking_offset) in added_cameras.iter() {
         info!("detected added camera, updating proxy");
+        *main_rig = rig.cloned().unwrap_or_default();
+        if let Some(tracking_offset) = tracking_offset {
+            main_rig.follow_offset = tracking_offset.0;
+        }
+        *look_transform = LookTransform::new(
+            transform.translation,
+            transform.translation + transform.forward(),
+            Vec3::Y,
+        );
+
         camera.hdr = true;
         commands
-            .entity(entity)
+            .entity(main_camera)
+            // a fresh smoother starts from the new position instead of gliding to it
+            .insert(Smoother::new(main_rig.smoothing))
             .insert(DebandDither::Enabled)
             .insert(Tonemapping::BlenderFilmic)
             .insert(BloomSettings {
@@ -20,5 +56,13 @@ pub fn camera_replace_proxies(
                 composite_mode: BloomCompositeMode::Additive,
                 ..default()
             });
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// the next level might not come with a camera of its own
+pub fn reset_camera_rig(mut rigs: Query<&mut CameraRig, With<MainCamera>>) {
+    for mut rig in rigs.iter_mut() {
+        *rig = CameraRig::default();
     }
 }
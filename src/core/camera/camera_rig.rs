@@ -0,0 +1,233 @@
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use smooth_bevy_cameras::{LookTransform, LookTransformBundle, Smoother};
+
+use super::CameraTrackable;
+
+// how far the free orbit camera can zoom in & out
+const MIN_ORBIT_DISTANCE: f32 = 2.0;
+const MAX_ORBIT_DISTANCE: f32 = 100.0;
+const ORBIT_SENSITIVITY: f32 = 0.005;
+const ZOOM_SENSITIVITY: f32 = 1.0;
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+/// The camera everything is seen through, spawned once at startup
+pub struct MainCamera;
+
+#[derive(Reflect, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    /// behind the tracked entity (see `CameraTrackable`), usually the player
+    #[default]
+    FollowPlayer,
+    /// looking down on the level to build towers, moved around like in a strategy game (see `strategy_camera_input`)
+    Overview,
+    /// around the tracked entity (or the overview center), rotated with the middle mouse button
+    FreeOrbit,
+}
+
+impl CameraMode {
+    pub fn next(self) -> Self {
+        match self {
+            CameraMode::FollowPlayer => CameraMode::Overview,
+            CameraMode::Overview => CameraMode::FreeOrbit,
+            CameraMode::FreeOrbit => CameraMode::FollowPlayer,
+        }
+    }
+}
+
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component, Default)]
+/// How the main camera moves: add it to a camera in a level to override the defaults,
+/// the main camera then takes over its settings & starting position
+pub struct CameraRig {
+    pub mode: CameraMode,
+    /// from the tracked entity to the camera
+    pub follow_offset: Vec3,
+    /// from the tracked entity to what the camera looks at
+    pub follow_look_offset: Vec3,
+    /// what the camera looks at in overview, & orbits around when nothing is tracked
+    pub overview_center: Vec3,
+    /// from the overview center to the camera, before rotating it by `overview_yaw`
+    pub overview_offset: Vec3,
+    /// radians
+    pub overview_yaw: f32,
+    /// how close to the overview center the camera can zoom in
+    pub min_height: f32,
+    /// how far from the overview center the camera can zoom out
+    pub max_height: f32,
+    pub orbit_distance: f32,
+    /// radians
+    pub orbit_yaw: f32,
+    /// radians, above the horizon
+    pub orbit_pitch: f32,
+    /// lag weight of the camera's `Smoother`: 0 snaps the camera where it should be, closer to 1 makes it lag behind
+    pub smoothing: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        CameraRig {
+            mode: CameraMode::FollowPlayer,
+            follow_offset: Vec3::new(0.0, 8.0, 16.0),
+            follow_look_offset: Vec3::new(0.0, 5.0, 2.0),
+            overview_center: Vec3::ZERO,
+            overview_offset: Vec3::new(0.0, 40.0, 20.0),
+            overview_yaw: 0.0,
+            min_height: 8.0,
+            max_height: 80.0,
+            orbit_distance: 20.0,
+            orbit_yaw: 0.0,
+            orbit_pitch: 0.6,
+            smoothing: 0.5,
+        }
+    }
+}
+
+pub fn spawn_main_camera(mut commands: Commands) {
+    let rig = CameraRig::default();
+    commands.spawn((
+        LookTransformBundle {
+            transform: LookTransform::new(
+                Vec3::new(-2.0, 2.5, 5.0),
+                Vec3::new(0.0, 0.5, 0.0),
+                Vec3::Y,
+            ),
+            smoother: Smoother::new(rig.smoothing),
+        },
+        Camera3dBundle::default(),
+        MainCamera,
+        rig,
+        // sound cues are heard from the camera
+        SpatialListener::new(0.3),
+        Name::new("MainCamera"),
+    ));
+}
+
+pub fn cycle_camera_mode(keycode: Res<Input<KeyCode>>, mut rigs: Query<&mut CameraRig>) {
+    if keycode.just_pressed(KeyCode::C) {
+        for mut rig in rigs.iter_mut() {
+            rig.mode = rig.mode.next();
+            info!("camera mode: {:?}", rig.mode);
+        }
+    }
+}
+
+pub fn free_orbit_input(
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut rigs: Query<&mut CameraRig>,
+) {
+    let motion: Vec2 = mouse_motion_events.read().map(|event| event.delta).sum();
+    let scroll: f32 = mouse_wheel_events.read().map(|event| event.y).sum();
+    for mut rig in rigs.iter_mut() {
+        if rig.mode != CameraMode::FreeOrbit {
+            continue;
+        }
+        if mouse_buttons.pressed(MouseButton::Middle) {
+            rig.orbit_yaw -= motion.x * ORBIT_SENSITIVITY;
+            rig.orbit_pitch = (rig.orbit_pitch + motion.y * ORBIT_SENSITIVITY).clamp(-1.4, 1.4);
+        }
+        rig.orbit_distance = (rig.orbit_distance - scroll * ZOOM_SENSITIVITY)
+            .clamp(MIN_ORBIT_DISTANCE, MAX_ORBIT_DISTANCE);
+    }
+}
+
+// the LookTransformPlugin then moves the camera there, smoothly
+pub fn update_camera_rig(
+    tracked: Query<&GlobalTransform, With<CameraTrackable>>,
+    mut cameras: Query<(&mut LookTransform, &CameraRig), Without<CameraTrackable>>,
+) {
+    let tracked = tracked
+        .iter()
+        .next()
+        .map(|transform| transform.translation());
+    for (mut look_transform, rig) in cameras.iter_mut() {
+        let (eye, target) = match rig.mode {
+            CameraMode::FollowPlayer => {
+                let Some(tracked) = tracked else {
+                    continue;
+                };
+                (
+                    tracked + rig.follow_offset,
+                    tracked + rig.follow_look_offset,
+                )
+            }
+            CameraMode::Overview => (
+                rig.overview_center + Quat::from_rotation_y(rig.overview_yaw) * rig.overview_offset,
+                rig.overview_center,
+            ),
+            CameraMode::FreeOrbit => {
+                let pivot = tracked.unwrap_or(rig.overview_center);
+                let rotation =
+                    Quat::from_euler(EulerRot::YXZ, rig.orbit_yaw, -rig.orbit_pitch, 0.0);
+                (pivot + rotation * Vec3::Z * rig.orbit_distance, pivot)
+            }
+        };
+        look_transform.eye = eye;
+        look_transform.target = target;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eye_and_target(app: &mut App) -> (Vec3, Vec3) {
+        let look_transform = app.world.query::<&LookTransform>().single(&app.world);
+        (look_transform.eye, look_transform.target)
+    }
+
+    fn press_c(app: &mut App) {
+        app.world.resource_mut::<Input<KeyCode>>().press(KeyCode::C);
+        app.update();
+        let mut keycode = app.world.resource_mut::<Input<KeyCode>>();
+        keycode.release(KeyCode::C);
+        keycode.clear();
+    }
+
+    #[test]
+    fn the_camera_cycles_through_its_modes() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Input<KeyCode>>()
+            .add_systems(Update, (cycle_camera_mode, update_camera_rig).chain());
+        let player = Vec3::new(10.0, 0.0, 0.0);
+        app.world
+            .spawn((CameraTrackable, GlobalTransform::from_translation(player)));
+        app.world.spawn((
+            LookTransform::new(Vec3::ZERO, Vec3::Z, Vec3::Y),
+            CameraRig::default(),
+        ));
+        let rig = CameraRig::default();
+
+        app.update();
+        assert_eq!(
+            eye_and_target(&mut app),
+            (player + rig.follow_offset, player + rig.follow_look_offset)
+        );
+
+        press_c(&mut app);
+        assert_eq!(
+            eye_and_target(&mut app),
+            (
+                rig.overview_center + rig.overview_offset,
+                rig.overview_center
+            )
+        );
+
+        // orbits around the player
+        press_c(&mut app);
+        let (eye, target) = eye_and_target(&mut app);
+        assert_eq!(target, player);
+        assert!((eye.distance(player) - rig.orbit_distance).abs() < 1e-4);
+        assert!(eye.y > player.y);
+
+        press_c(&mut app);
+        assert_eq!(
+            app.world.query::<&CameraRig>().single(&app.world).mode,
+            CameraMode::FollowPlayer
+        );
+    }
+}
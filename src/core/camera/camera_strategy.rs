@@ -0,0 +1,199 @@
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use super::{CameraMode, CameraRig, MainCamera};
+
+// ground covered per second for each unit of height, so panning feels the same at any zoom level
+const PAN_SPEED: f32 = 1.0;
+// how close to the edges of the window the cursor pans the camera, in pixels
+const EDGE_PAN_MARGIN: f32 = 10.0;
+const DRAG_PAN_SENSITIVITY: f32 = 0.002;
+// radians per second
+const ROTATE_SPEED: f32 = 1.5;
+// fraction of the distance zoomed by each step of the mouse wheel
+const ZOOM_STEP: f32 = 0.1;
+// the overview camera never gets closer to its focus than this, whatever the rig says
+const MIN_OVERVIEW_HEIGHT: f32 = 1.0;
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+/// Add this to a cube in a level (2 units wide before scaling, like Blender's default cube):
+/// the strategy camera's focus & height are kept inside of it
+pub struct CameraBounds;
+
+// WASD/arrows or the edges of the window pan, dragging with the middle mouse button too,
+// Q/E rotate around the focus point & the mouse wheel zooms
+#[allow(clippy::too_many_arguments)]
+pub fn strategy_camera_input(
+    time: Res<Time>,
+    keycode: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    mut mouse_motion_events: EventReader<MouseMotion>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    bounds: Query<&GlobalTransform, With<CameraBounds>>,
+    mut rigs: Query<&mut CameraRig, With<MainCamera>>,
+) {
+    let motion: Vec2 = mouse_motion_events.read().map(|event| event.delta).sum();
+    let scroll: f32 = mouse_wheel_events.read().map(|event| event.y).sum();
+    let delta = time.delta_seconds();
+
+    for mut rig in rigs.iter_mut() {
+        if rig.mode != CameraMode::Overview {
+            continue;
+        }
+
+        if keycode.pressed(KeyCode::Q) {
+            rig.overview_yaw += ROTATE_SPEED * delta;
+        }
+        if keycode.pressed(KeyCode::E) {
+            rig.overview_yaw -= ROTATE_SPEED * delta;
+        }
+
+        if scroll != 0.0 {
+            let zoom = (1.0 - scroll * ZOOM_STEP).max(0.1);
+            rig.overview_offset *= zoom;
+        }
+
+        // panning follows the camera's rotation, the top of the screen is "forward"
+        let rotation = Quat::from_rotation_y(rig.overview_yaw);
+        let forward = rotation * Vec3::NEG_Z;
+        let right = rotation * Vec3::X;
+        let height = rig.overview_offset.y.max(1.0);
+
+        let mut pan = Vec2::ZERO;
+        if keycode.any_pressed([KeyCode::W, KeyCode::Up]) {
+            pan.y += 1.0;
+        }
+        if keycode.any_pressed([KeyCode::S, KeyCode::Down]) {
+            pan.y -= 1.0;
+        }
+        if keycode.any_pressed([KeyCode::A, KeyCode::Left]) {
+            pan.x -= 1.0;
+        }
+        if keycode.any_pressed([KeyCode::D, KeyCode::Right]) {
+            pan.x += 1.0;
+        }
+        if let Some((window, cursor)) = windows
+            .get_single()
+            .ok()
+            .and_then(|window| window.cursor_position().map(|cursor| (window, cursor)))
+        {
+            if cursor.x < EDGE_PAN_MARGIN {
+                pan.x -= 1.0;
+            } else if cursor.x > window.width() - EDGE_PAN_MARGIN {
+                pan.x += 1.0;
+            }
+            if cursor.y < EDGE_PAN_MARGIN {
+                pan.y += 1.0;
+            } else if cursor.y > window.height() - EDGE_PAN_MARGIN {
+                pan.y -= 1.0;
+            }
+        }
+        let pan = pan.clamp_length_max(1.0);
+        let mut focus =
+            rig.overview_center + (right * pan.x + forward * pan.y) * PAN_SPEED * height * delta;
+
+        // the ground follows the cursor while dragging
+        if mouse_buttons.pressed(MouseButton::Middle) {
+            focus += (forward * motion.y - right * motion.x) * DRAG_PAN_SENSITIVITY * height;
+        }
+
+        // the camera never goes lower than its minimum height, even with the focus at the top of the bounds
+        let min_height = rig.min_height.max(MIN_OVERVIEW_HEIGHT);
+        let mut max_height = rig.max_height;
+        if let Some(bounds) = bounds.iter().next() {
+            let (scale, _, center) = bounds.to_scale_rotation_translation();
+            let (min, max) = (center - scale.abs(), center + scale.abs());
+            focus = focus.clamp(min, max);
+            max_height = max_height.min(max.y - focus.y);
+        }
+        let max_height = max_height.max(min_height);
+        rig.overview_center = focus;
+
+        // zooming scales the offset along the same direction, so the viewing angle stays the same
+        let direction = overview_direction(rig.overview_offset);
+        let height = rig.overview_offset.y.clamp(min_height, max_height);
+        rig.overview_offset = direction * (height / direction.y);
+    }
+}
+
+// the direction from the overview center to the camera, the default one if the offset does not look down
+fn overview_direction(offset: Vec3) -> Vec3 {
+    offset
+        .try_normalize()
+        .filter(|direction| direction.y > 0.0)
+        .unwrap_or_else(|| CameraRig::default().overview_offset.normalize())
+}
+
+/// Whether the player can be controlled, the strategy camera takes over the movement keys
+pub fn player_controls_enabled(rigs: Query<&CameraRig, With<MainCamera>>) -> bool {
+    rigs.iter().all(|rig| rig.mode != CameraMode::Overview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overview_app(rig: CameraRig) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .add_event::<MouseMotion>()
+            .add_event::<MouseWheel>()
+            .add_systems(Update, strategy_camera_input);
+        app.world.spawn((
+            CameraBounds,
+            GlobalTransform::from(Transform::from_scale(Vec3::new(20.0, 5.0, 20.0))),
+        ));
+        app.world.spawn((
+            MainCamera,
+            CameraRig {
+                mode: CameraMode::Overview,
+                ..rig
+            },
+        ));
+        app
+    }
+
+    fn overview_offset(app: &mut App) -> Vec3 {
+        app.world
+            .query::<&CameraRig>()
+            .single(&app.world)
+            .overview_offset
+    }
+
+    #[test]
+    fn overview_offset_survives_a_focus_at_the_top_of_the_bounds() {
+        let mut app = overview_app(CameraRig {
+            overview_center: Vec3::new(0.0, 50.0, 0.0),
+            ..default()
+        });
+        let direction = CameraRig::default().overview_offset.normalize();
+        for _ in 0..3 {
+            app.update();
+            let offset = overview_offset(&mut app);
+            assert!(offset.is_finite());
+            assert!(offset.y >= CameraRig::default().min_height);
+            assert!(offset.normalize().abs_diff_eq(direction, 1e-5));
+        }
+        let rig = app.world.query::<&CameraRig>().single(&app.world);
+        assert_eq!(rig.overview_center.y, 5.0);
+    }
+
+    #[test]
+    fn a_zero_overview_offset_gets_its_direction_back() {
+        let mut app = overview_app(CameraRig {
+            overview_offset: Vec3::ZERO,
+            ..default()
+        });
+        app.update();
+        let offset = overview_offset(&mut app);
+        assert!(offset.y >= CameraRig::default().min_height);
+        assert!(offset
+            .normalize()
+            .abs_diff_eq(CameraRig::default().overview_offset.normalize(), 1e-5));
+    }
+}
@@ -4,21 +4,46 @@ pub use camera_tracking::*;
 pub mod camera_replace_proxies;
 pub use camera_replace_proxies::*;
 
+pub mod camera_rig;
+pub use camera_rig::*;
+
+pub mod camera_strategy;
+pub use camera_strategy::*;
+
+pub mod camera_occlusion;
+pub use camera_occlusion::*;
+
 use bevy::prelude::*;
 use bevy_gltf_blueprints::GltfBlueprintsSet;
+use smooth_bevy_cameras::{look_transform_system, LookTransformPlugin};
+
+use crate::state::{AppState, GameState};
 
 pub struct CameraPlugin;
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<CameraTrackable>()
-            .register_type::<CameraTracking>()
+        app.add_plugins(LookTransformPlugin)
+            .register_type::<CameraTrackable>()
             .register_type::<CameraTrackingOffset>()
+            .register_type::<MainCamera>()
+            .register_type::<CameraMode>()
+            .register_type::<CameraRig>()
+            .register_type::<CameraBounds>()
+            .register_type::<CameraOccluder>()
+            .add_systems(Startup, spawn_main_camera)
             .add_systems(
                 Update,
                 (
                     camera_replace_proxies.after(GltfBlueprintsSet::AfterSpawn),
-                    camera_track,
-                ),
-            );
+                    (cycle_camera_mode, free_orbit_input, strategy_camera_input)
+                        .run_if(in_state(GameState::InGame)),
+                    update_camera_rig,
+                    camera_collision,
+                    fade_camera_occluders,
+                )
+                    .chain()
+                    .before(look_transform_system),
+            )
+            .add_systems(OnExit(AppState::AppRunning), reset_camera_rig);
     }
 }
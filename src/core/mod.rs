@@ -7,18 +7,24 @@ pub use relationships::*;
 pub mod physics;
 pub use physics::*;
 
+pub mod camera;
+pub use camera::*;
+
 use bevy::prelude::*;
 use bevy_gltf_blueprints::*;
 
+/// Where the blueprints are looked up, relative to the assets folder
+pub const BLUEPRINTS_LIBRARY_FOLDER: &str = "models/library";
+
 pub struct CorePlugin;
 impl Plugin for CorePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
             LightingPlugin,
-            //CameraPlugin,
+            CameraPlugin,
             PhysicsPlugin,
             BlueprintsPlugin {
-                library_folder: "models/library".into(),
+                library_folder: BLUEPRINTS_LIBRARY_FOLDER.into(),
                 ..Default::default()
             },
         ));
@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_xpbd_3d::prelude::*;
+
+use crate::core::find_in_ancestors;
+
+#[derive(PhysicsLayer, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameLayer {
+    #[default]
+    Terrain,
+    Player,
+    Enemy,
+    Projectile,
+    Tower,
+    Pickup,
+    Sensor,
+}
+
+impl GameLayer {
+    pub const ALL: [GameLayer; 7] = [
+        GameLayer::Terrain,
+        GameLayer::Player,
+        GameLayer::Enemy,
+        GameLayer::Projectile,
+        GameLayer::Tower,
+        GameLayer::Pickup,
+        GameLayer::Sensor,
+    ];
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+/// The layers a blueprint belongs to, its colliders (its own & its descendants') get xpbd `CollisionLayers`
+/// with the masks of the `CollisionLayerMatrix`. Colliders without layers collide with everything
+pub struct CollisionLayersProxy(pub Vec<GameLayer>);
+
+/// Which layers collide with each other, everything does unless told otherwise
+#[derive(Resource, Debug, Clone)]
+pub struct CollisionLayerMatrix {
+    ignored: HashSet<(GameLayer, GameLayer)>,
+}
+
+impl Default for CollisionLayerMatrix {
+    fn default() -> Self {
+        let mut matrix = CollisionLayerMatrix {
+            ignored: HashSet::new(),
+        };
+        // towers are not hit by projectiles, projectiles do not hit each other
+        matrix.set(GameLayer::Projectile, GameLayer::Tower, false);
+        matrix.set(GameLayer::Projectile, GameLayer::Projectile, false);
+        matrix.set(GameLayer::Projectile, GameLayer::Pickup, false);
+        // enemies walk through each other along their paths
+        matrix.set(GameLayer::Enemy, GameLayer::Enemy, false);
+        matrix.set(GameLayer::Sensor, GameLayer::Sensor, false);
+        matrix.set(GameLayer::Sensor, GameLayer::Terrain, false);
+        matrix
+    }
+}
+
+impl CollisionLayerMatrix {
+    fn key(a: GameLayer, b: GameLayer) -> (GameLayer, GameLayer) {
+        if (a as u32) <= (b as u32) {
+            (a, b)
+        } else {
+            (b, a)
+        }
+    }
+
+    pub fn set(&mut self, a: GameLayer, b: GameLayer, collide: bool) {
+        if collide {
+            self.ignored.remove(&Self::key(a, b));
+        } else {
+            self.ignored.insert(Self::key(a, b));
+        }
+    }
+
+    pub fn collides(&self, a: GameLayer, b: GameLayer) -> bool {
+        !self.ignored.contains(&Self::key(a, b))
+    }
+
+    /// Every layer colliding with at least one of `layers`
+    pub fn masks(&self, layers: &[GameLayer]) -> Vec<GameLayer> {
+        GameLayer::ALL
+            .into_iter()
+            .filter(|other| layers.iter().any(|layer| self.collides(*layer, *other)))
+            .collect()
+    }
+
+    pub fn collision_layers(&self, layers: &[GameLayer]) -> CollisionLayers {
+        CollisionLayers::new(layers.iter().copied(), self.masks(layers))
+    }
+
+    /// A filter for ray & shape casts made on behalf of something on `layer`
+    pub fn query_filter(&self, layer: GameLayer) -> SpatialQueryFilter {
+        SpatialQueryFilter::new().with_masks(self.masks(&[layer]))
+    }
+}
+
+// colliders get the layers of the closest CollisionLayersProxy, on themselves or on one of their ancestors
+pub fn apply_collision_layers(
+    matrix: Res<CollisionLayerMatrix>,
+    colliders: Query<Entity, Added<Collider>>,
+    layer_proxies: Query<&CollisionLayersProxy>,
+    parents: Query<&Parent>,
+    mut commands: Commands,
+) {
+    for collider in colliders.iter() {
+        let Some(CollisionLayersProxy(layers)) =
+            find_in_ancestors(collider, &parents, |entity| layer_proxies.contains(entity))
+                .and_then(|entity| layer_proxies.get(entity).ok())
+        else {
+            continue;
+        };
+        commands
+            .entity(collider)
+            .insert(matrix.collision_layers(layers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_matrix_filters_both_ways() {
+        let mut matrix = CollisionLayerMatrix::default();
+        assert!(!matrix.collides(GameLayer::Tower, GameLayer::Projectile));
+        assert!(!matrix.collides(GameLayer::Projectile, GameLayer::Tower));
+        assert!(matrix.collides(GameLayer::Enemy, GameLayer::Projectile));
+
+        let projectile = matrix.collision_layers(&[GameLayer::Projectile]);
+        let tower = matrix.collision_layers(&[GameLayer::Tower]);
+        let enemy = matrix.collision_layers(&[GameLayer::Enemy]);
+        assert!(!projectile.interacts_with(tower));
+        assert!(projectile.interacts_with(enemy));
+        assert!(!enemy.interacts_with(enemy));
+
+        matrix.set(GameLayer::Tower, GameLayer::Projectile, true);
+        assert!(matrix
+            .collision_layers(&[GameLayer::Projectile])
+            .interacts_with(matrix.collision_layers(&[GameLayer::Tower])));
+    }
+
+    #[test]
+    fn colliders_get_the_layers_of_their_closest_proxy() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<CollisionLayerMatrix>()
+            .add_systems(Update, apply_collision_layers);
+        let tower = app
+            .world
+            .spawn(CollisionLayersProxy(vec![GameLayer::Tower]))
+            .with_children(|tower| {
+                tower.spawn(SpatialBundle::default()).with_children(|mesh| {
+                    mesh.spawn(Collider::ball(1.0));
+                });
+            })
+            .id();
+        let unlayered = app.world.spawn(Collider::ball(1.0)).id();
+        app.update();
+
+        let mut layered = app
+            .world
+            .query_filtered::<(Entity, &CollisionLayers), With<Collider>>();
+        let (collider, layers) = layered.single(&app.world);
+        assert_ne!(collider, unlayered);
+        assert_ne!(collider, tower);
+        assert!(layers.contains_group(GameLayer::Tower));
+        assert!(!layers.contains_mask(GameLayer::Projectile));
+        assert!(layers.contains_mask(GameLayer::Enemy));
+    }
+}
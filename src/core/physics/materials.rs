@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_xpbd_3d::prelude::*;
+use serde::Deserialize;
+
+use crate::core::find_in_ancestors;
+
+#[derive(Component, Reflect, Deserialize, Debug, Clone, Copy)]
+#[reflect(Component, Default)]
+/// How a blueprint's colliders behave on contact, applied to its own & its descendants' colliders
+pub struct PhysicsMaterial {
+    pub friction: f32,
+    pub restitution: f32,
+    /// used with the collider's volume to compute its mass
+    pub density: f32,
+}
+
+// the xpbd defaults
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        PhysicsMaterial {
+            friction: 0.3,
+            restitution: 0.0,
+            density: 1.0,
+        }
+    }
+}
+
+// colliders get the material of the closest PhysicsMaterial proxy, on themselves or on one of their ancestors,
+// rigid bodies carrying a proxy get its friction & restitution too. Materials can arrive after the colliders
+// (ie the ones derived from a SoundMaterial once the table is loaded), so they get pushed down again on change
+pub fn apply_physics_materials(
+    colliders: Query<Entity, Added<Collider>>,
+    changed_materials: Query<(Entity, &PhysicsMaterial, Has<RigidBody>), Changed<PhysicsMaterial>>,
+    all_colliders: Query<(), With<Collider>>,
+    materials: Query<&PhysicsMaterial>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    mut commands: Commands,
+) {
+    let mut updated: HashSet<Entity> = colliders.iter().collect();
+    for (entity, material, is_body) in changed_materials.iter() {
+        updated.extend(
+            std::iter::once(entity)
+                .chain(children.iter_descendants(entity))
+                .filter(|entity| all_colliders.contains(*entity)),
+        );
+        if is_body {
+            commands.entity(entity).insert((
+                Friction::new(material.friction),
+                Restitution::new(material.restitution),
+            ));
+        }
+    }
+
+    for collider in updated {
+        let Some(material) =
+            find_in_ancestors(collider, &parents, |entity| materials.contains(entity))
+                .and_then(|entity| materials.get(entity).ok())
+        else {
+            continue;
+        };
+        commands.entity(collider).insert((
+            Friction::new(material.friction),
+            Restitution::new(material.restitution),
+            ColliderDensity(material.density),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn late_materials_reach_the_colliders_below_them() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_systems(Update, apply_physics_materials);
+        let mut collider = Entity::PLACEHOLDER;
+        let prop = app
+            .world
+            .spawn(TransformBundle::default())
+            .with_children(|prop| {
+                collider = prop.spawn(Collider::ball(1.0)).id();
+            })
+            .id();
+        app.update();
+        assert!(app.world.get::<Friction>(collider).is_none());
+
+        app.world.entity_mut(prop).insert(PhysicsMaterial {
+            friction: 0.8,
+            restitution: 0.4,
+            density: 2.5,
+        });
+        app.update();
+        let collider = app.world.entity(collider);
+        assert_eq!(collider.get::<Friction>().unwrap().dynamic_coefficient, 0.8);
+        assert_eq!(collider.get::<Restitution>().unwrap().coefficient, 0.4);
+        assert_eq!(collider.get::<ColliderDensity>().unwrap().0, 2.5);
+    }
+}
@@ -6,6 +6,15 @@ pub mod utils;
 pub mod controls;
 pub use controls::*;
 
+pub mod triggers;
+pub use triggers::*;
+
+pub mod layers;
+pub use layers::*;
+
+pub mod materials;
+pub use materials::*;
+
 use crate::state::GameState;
 
 use bevy::prelude::*;
@@ -19,10 +28,25 @@ impl Plugin for PhysicsPlugin {
         app.add_plugins((PhysicsPlugins::default(), PhysicsDebugPlugin::default()))
             .register_type::<AutoAABBCollider>()
             .register_type::<physics_replace_proxies::Collider>()
+            .register_type::<Sensor>()
+            .register_type::<TriggerZone>()
+            .register_type::<GameLayer>()
+            .register_type::<Vec<GameLayer>>()
+            .register_type::<layers::CollisionLayersProxy>()
+            .init_resource::<CollisionLayerMatrix>()
+            .register_type::<PhysicsMaterial>()
+            .add_event::<TriggerEntered>()
+            .add_event::<TriggerExited>()
+            .add_systems(
+                Update,
+                (physics_replace_proxies, auto_aabb_colliders)
+                    .after(GltfBlueprintsSet::AfterSpawn),
+            )
             .add_systems(
                 Update,
-                physics_replace_proxies.after(GltfBlueprintsSet::AfterSpawn),
+                (apply_collision_layers, apply_physics_materials).after(physics_replace_proxies),
             )
+            .add_systems(Update, trigger_zone_events)
             .add_systems(OnEnter(GameState::InGame), resume_physics)
             .add_systems(OnExit(GameState::InGame), pause_physics);
     }
@@ -1,21 +1,33 @@
 use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use bevy::utils::HashMap;
+use bevy_gltf_blueprints::SpawnHere;
 use bevy_xpbd_3d::prelude::Collider as XpbdCollider;
 use bevy_xpbd_3d::prelude::*;
 
 use super::utils::*;
+use crate::core::find_in_ancestors;
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub enum Collider {
     Ball(f32),
     Cuboid(Vec3),
     Capsule(Vec3, Vec3, f32),
     #[default]
     Mesh,
+    /// a single convex hull wrapping all the child meshes, much cheaper than a trimesh for dynamic props
+    ConvexHull,
+    /// the child meshes approximated by several convex parts, for concave props
+    ConvexDecomposition,
+    /// one convex hull per child mesh
+    Compound,
+    /// terrain sampled from the child meshes on a grid of the given resolution (points per side)
+    Heightfield(u32),
 }
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub enum AutoAABBCollider {
     #[default]
     Cuboid,
@@ -33,6 +45,7 @@ pub fn physics_replace_proxies(
     >,
     // needed for tri meshes
     children: Query<&Children>,
+    transforms: Query<(&Transform, &Parent)>,
 
     mut commands: Commands,
 ) {
@@ -42,6 +55,10 @@ pub fn physics_replace_proxies(
         if name.ends_with("_collider") || name.ends_with("_sensor") {
             *visibility = Visibility::Hidden;
         }
+        // sensors only detect overlaps (see TriggerZone), instead of blocking
+        if name.ends_with("_sensor") {
+            commands.entity(entity).insert(Sensor);
+        }
 
         let mut xpbd_collider: XpbdCollider;
         match collider_proxy {
@@ -82,6 +99,451 @@ pub fn physics_replace_proxies(
                     commands.entity(entity).insert(xpbd_collider);
                 }
             }
+            Collider::ConvexHull => {
+                info!("generating collider from proxy: convex hull");
+                let points =
+                    entity_space_meshes(entity, &children, &meshes, &mesh_handles, &transforms)
+                        .iter()
+                        .flat_map(mesh_positions)
+                        .collect();
+                match XpbdCollider::convex_hull(points) {
+                    Some(xpbd_collider) => {
+                        commands.entity(entity).insert(xpbd_collider);
+                    }
+                    None => warn!("could not compute the convex hull of {}", name),
+                }
+            }
+            Collider::ConvexDecomposition => {
+                info!("generating collider from proxy: convex decomposition");
+                // a decomposition is already a compound & compounds cannot be nested: one per child mesh
+                let mut decomposed = false;
+                for (mesh_entity, collider_mesh) in
+                    Mesh::search_in_children(entity, &children, &meshes, &mesh_handles)
+                {
+                    if let Some(xpbd_collider) =
+                        XpbdCollider::convex_decomposition_from_mesh(collider_mesh)
+                    {
+                        let mut mesh_commands = commands.entity(mesh_entity);
+                        mesh_commands.insert(xpbd_collider);
+                        if name.ends_with("_sensor") {
+                            mesh_commands.insert(Sensor);
+                        }
+                        decomposed = true;
+                    }
+                }
+                if !decomposed {
+                    warn!("could not compute the convex decomposition of {}", name);
+                }
+            }
+            Collider::Compound => {
+                info!("generating collider from proxy: compound");
+                let parts: Vec<_> =
+                    entity_space_meshes(entity, &children, &meshes, &mesh_handles, &transforms)
+                        .iter()
+                        .filter_map(XpbdCollider::convex_hull_from_mesh)
+                        .map(|part| (Position::default(), Rotation::default(), part))
+                        .collect();
+                if parts.is_empty() {
+                    warn!("could not compute the compound collider of {}", name);
+                } else {
+                    commands
+                        .entity(entity)
+                        .insert(XpbdCollider::compound(parts));
+                }
+            }
+            Collider::Heightfield(resolution) => {
+                info!("generating collider from proxy: heightfield");
+                let collider_meshes =
+                    entity_space_meshes(entity, &children, &meshes, &mesh_handles, &transforms);
+                match heightfield_from_meshes(&collider_meshes, (*resolution).max(2) as usize) {
+                    Some((xpbd_collider, center)) => {
+                        // heightfields cannot go into a compound, so the offset goes on a child instead
+                        commands.entity(entity).with_children(|parent| {
+                            let mut heightfield = parent.spawn((
+                                Name::new(format!("{}_heightfield", name)),
+                                TransformBundle::from_transform(Transform::from_translation(
+                                    center,
+                                )),
+                                xpbd_collider,
+                            ));
+                            if name.ends_with("_sensor") {
+                                heightfield.insert(Sensor);
+                            }
+                        });
+                    }
+                    None => warn!("could not compute the heightfield of {}", name),
+                }
+            }
+        }
+    }
+}
+
+// transform of `entity` relative to its `ancestor`, built from the local transforms as the global ones
+// of freshly spawned blueprints have not been propagated yet
+fn transform_relative_to(
+    entity: Entity,
+    ancestor: Entity,
+    transforms: &Query<(&Transform, &Parent)>,
+) -> Transform {
+    let mut relative_transform = Transform::IDENTITY;
+    let mut current = entity;
+    while current != ancestor {
+        let Ok((transform, parent)) = transforms.get(current) else {
+            break;
+        };
+        relative_transform = transform.mul_transform(relative_transform);
+        current = parent.get();
+    }
+    relative_transform
+}
+
+// the child meshes of `entity`, moved into its space so they can be combined into a single collider
+fn entity_space_meshes(
+    entity: Entity,
+    children: &Query<&Children>,
+    meshes: &Assets<Mesh>,
+    mesh_handles: &Query<&Handle<Mesh>>,
+    transforms: &Query<(&Transform, &Parent)>,
+) -> Vec<Mesh> {
+    Mesh::search_in_children(entity, children, meshes, mesh_handles)
+        .into_iter()
+        .map(|(mesh_entity, mesh)| {
+            mesh.transformed(transform_relative_to(mesh_entity, entity, transforms))
+        })
+        .collect()
+}
+
+fn mesh_positions(mesh: &Mesh) -> Vec<Vec3> {
+    match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        Some(VertexAttributeValues::Float32x3(positions)) => {
+            positions.iter().copied().map(Vec3::from).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// samples the height of the meshes' triangles on a regular grid, covering the horizontal bounds of the meshes,
+// returns the heightfield along with the point it has to be centered on
+fn heightfield_from_meshes(meshes: &[Mesh], resolution: usize) -> Option<(XpbdCollider, Vec3)> {
+    let mut triangles = Vec::new();
+    for mesh in meshes {
+        let positions = mesh_positions(mesh);
+        let indices: Vec<usize> = match mesh.indices() {
+            Some(indices) => indices.iter().collect(),
+            None => (0..positions.len()).collect(),
+        };
+        for triangle in indices.chunks_exact(3) {
+            triangles.push([
+                positions[triangle[0]],
+                positions[triangle[1]],
+                positions[triangle[2]],
+            ]);
+        }
+    }
+
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for point in triangles.iter().flatten() {
+        min = min.min(*point);
+        max = max.max(*point);
+    }
+    let size = max - min;
+    if triangles.is_empty() || size.x.is_approx_zero() || size.z.is_approx_zero() {
+        return None;
+    }
+
+    // heights are indexed [x][z], points outside of every triangle stay at the lowest height
+    let step = Vec2::new(size.x, size.z) / (resolution - 1) as f32;
+    let mut heights = vec![vec![f32::MIN; resolution]; resolution];
+    for [a, b, c] in triangles {
+        let (a2, b2, c2) = (a.xz(), b.xz(), c.xz());
+        let area = (b2 - a2).perp_dot(c2 - a2);
+        if area.is_approx_zero() {
+            // vertical triangle, it does not add any height of its own
+            continue;
+        }
+        let triangle_min = (a2.min(b2).min(c2) - min.xz()) / step;
+        let triangle_max = (a2.max(b2).max(c2) - min.xz()) / step;
+        for x in (triangle_min.x.ceil().max(0.0) as usize)
+            ..=(triangle_max.x.floor() as usize).min(resolution - 1)
+        {
+            for z in (triangle_min.y.ceil().max(0.0) as usize)
+                ..=(triangle_max.y.floor() as usize).min(resolution - 1)
+            {
+                let point = min.xz() + Vec2::new(x as f32, z as f32) * step;
+                let weight_a = (b2 - point).perp_dot(c2 - point) / area;
+                let weight_b = (c2 - point).perp_dot(a2 - point) / area;
+                let weight_c = 1.0 - weight_a - weight_b;
+                if weight_a < -1e-4 || weight_b < -1e-4 || weight_c < -1e-4 {
+                    continue;
+                }
+                let height = a.y * weight_a + b.y * weight_b + c.y * weight_c;
+                heights[x][z] = heights[x][z].max(height);
+            }
         }
     }
+    for height in heights.iter_mut().flatten() {
+        if *height == f32::MIN {
+            *height = min.y;
+        }
+    }
+
+    // xpbd heightfields are centered horizontally on their origin
+    let center = Vec3::new((min.x + max.x) / 2.0, 0.0, (min.z + max.z) / 2.0);
+    let heightfield = XpbdCollider::heightfield(heights, Vec3::new(size.x, 1.0, size.z));
+    Some((heightfield, center))
+}
+
+// how many frames to wait for the child meshes of a spawned blueprint, before giving up on fitting its collider
+const AUTO_AABB_ATTEMPTS: u32 = 10;
+
+// fits a collider to the bounds of the child meshes, once the blueprint they belong to is done spawning
+pub fn auto_aabb_colliders(
+    meshes: Res<Assets<Mesh>>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    added_colliders: Query<Entity, Added<AutoAABBCollider>>,
+    auto_colliders: Query<&AutoAABBCollider, Without<XpbdCollider>>,
+    spawning: Query<(), With<SpawnHere>>,
+    parents: Query<&Parent>,
+    children: Query<&Children>,
+    transforms: Query<(&Transform, &Parent)>,
+    // attempts made so far, for each collider waiting for its meshes
+    mut pending: Local<HashMap<Entity, u32>>,
+
+    mut commands: Commands,
+) {
+    pending.extend(added_colliders.iter().map(|entity| (entity, 0)));
+    pending.retain(|entity, attempts| {
+        let Ok(auto_collider) = auto_colliders.get(*entity) else {
+            // despawned, removed or already has a collider
+            return false;
+        };
+        if find_in_ancestors(*entity, &parents, |entity| spawning.contains(entity)).is_some() {
+            // the meshes of the blueprint are not there yet
+            return true;
+        }
+        let Some((min, max)) =
+            child_meshes_bounds(*entity, &children, &meshes, &mesh_handles, &transforms)
+        else {
+            *attempts += 1;
+            if *attempts >= AUTO_AABB_ATTEMPTS {
+                warn!(
+                    "no meshes to fit the {:?} collider of {:?} to",
+                    auto_collider, entity
+                );
+                return false;
+            }
+            return true;
+        };
+        commands
+            .entity(*entity)
+            .insert(aabb_collider(auto_collider, min, max));
+        false
+    });
+}
+
+// bounds of the child meshes, in the space of `entity`: xpbd applies its scale to the collider,
+// while the scale of the children is baked in here
+fn child_meshes_bounds(
+    entity: Entity,
+    children: &Query<&Children>,
+    meshes: &Assets<Mesh>,
+    mesh_handles: &Query<&Handle<Mesh>>,
+    transforms: &Query<(&Transform, &Parent)>,
+) -> Option<(Vec3, Vec3)> {
+    let mut min = Vec3::splat(f32::MAX);
+    let mut max = Vec3::splat(f32::MIN);
+    for (mesh_entity, mesh) in Mesh::search_in_children(entity, children, meshes, mesh_handles) {
+        let Some(aabb) = mesh.compute_aabb() else {
+            continue;
+        };
+        let mesh_transform = transform_relative_to(mesh_entity, entity, transforms);
+        let center = Vec3::from(aabb.center);
+        let half_extents = Vec3::from(aabb.half_extents);
+        for corner in [
+            Vec3::new(-1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(-1.0, 1.0, 1.0),
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(1.0, -1.0, 1.0),
+            Vec3::new(1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ] {
+            let point = mesh_transform.transform_point(center + half_extents * corner);
+            min = min.min(point);
+            max = max.max(point);
+        }
+    }
+    (!min.cmpgt(max).any()).then_some((min, max))
+}
+
+fn aabb_collider(auto_collider: &AutoAABBCollider, min: Vec3, max: Vec3) -> XpbdCollider {
+    let size = max - min;
+    let center = (min + max) / 2.0;
+    let shape = match auto_collider {
+        AutoAABBCollider::Cuboid => {
+            info!("generating collider from mesh bounds: cuboid");
+            XpbdCollider::cuboid(size.x, size.y, size.z)
+        }
+        AutoAABBCollider::Ball => {
+            info!("generating collider from mesh bounds: ball");
+            XpbdCollider::ball(size.length() / 2.0)
+        }
+        AutoAABBCollider::Capsule => {
+            info!("generating collider from mesh bounds: capsule");
+            // upright, as wide as the widest horizontal side
+            let radius = size.x.max(size.z) / 2.0;
+            let height = (size.y - 2.0 * radius).max(0.0);
+            XpbdCollider::capsule(height, radius)
+        }
+    };
+    // the bounds are rarely centered on the origin of the entity
+    if center.is_approx_zero() {
+        shape
+    } else {
+        XpbdCollider::compound(vec![(Position(center), Rotation::default(), shape)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a proxy with two child meshes, run through the proxy replacement
+    fn replace_proxy(collider: Collider) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .add_systems(Update, physics_replace_proxies);
+
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let ground = meshes.add(Mesh::from(shape::Plane {
+            size: 4.0,
+            subdivisions: 3,
+        }));
+        let crate_mesh = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+        app.world
+            .spawn((
+                Name::new("Ground_collider"),
+                collider,
+                SpatialBundle::default(),
+            ))
+            .with_children(|parent| {
+                parent.spawn((ground, SpatialBundle::default()));
+                parent.spawn((
+                    crate_mesh,
+                    SpatialBundle::from_transform(Transform::from_xyz(3.0, 0.5, 0.0)),
+                ));
+            });
+
+        app.update();
+        app
+    }
+
+    #[test]
+    fn convex_decompositions_go_on_each_child_mesh() {
+        let mut app = replace_proxy(Collider::ConvexDecomposition);
+        let colliders = app
+            .world
+            .query_filtered::<Entity, (With<XpbdCollider>, With<Handle<Mesh>>)>()
+            .iter(&app.world)
+            .count();
+        assert_eq!(colliders, 2);
+    }
+
+    #[test]
+    fn heightfields_are_offset_by_a_child() {
+        let mut app = replace_proxy(Collider::Heightfield(8));
+        let (transform, _) = app
+            .world
+            .query::<(&Transform, &XpbdCollider)>()
+            .single(&app.world);
+        // the meshes span from -2 to 3.5 on x, & from -2 to 2 on z
+        assert!(transform
+            .translation
+            .abs_diff_eq(Vec3::new(0.75, 0.0, 0.0), 1e-5));
+    }
+
+    // a cube of size 1 under an auto collider, run through the fitting
+    fn fit_collider(auto_collider: AutoAABBCollider, cube_transform: Transform) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .add_systems(Update, auto_aabb_colliders);
+
+        let cube = app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::Cube { size: 1.0 }));
+        app.world
+            .spawn((
+                Name::new("Prop"),
+                auto_collider,
+                SpatialBundle::from_transform(Transform::from_scale(Vec3::splat(3.0))),
+                SpawnHere,
+            ))
+            .with_children(|parent| {
+                parent.spawn((cube, SpatialBundle::from_transform(cube_transform)));
+            });
+
+        // nothing gets fitted while the blueprint is spawning
+        app.update();
+        let mut colliders = app.world.query::<&XpbdCollider>();
+        assert_eq!(colliders.iter(&app.world).count(), 0);
+
+        let prop = app
+            .world
+            .query_filtered::<Entity, With<AutoAABBCollider>>()
+            .single(&app.world);
+        app.world.entity_mut(prop).remove::<SpawnHere>();
+        app.update();
+        app
+    }
+
+    fn fitted_collider(app: &mut App) -> &XpbdCollider {
+        app.world
+            .query_filtered::<&XpbdCollider, With<AutoAABBCollider>>()
+            .single(&app.world)
+    }
+
+    #[test]
+    fn cuboids_fit_the_scaled_meshes() {
+        let mut app = fit_collider(
+            AutoAABBCollider::Cuboid,
+            Transform::from_scale(Vec3::new(2.0, 1.0, 4.0)),
+        );
+        // the scale of the children is baked in, the one of the entity is left to xpbd
+        let cuboid = fitted_collider(&mut app).shape().as_cuboid().unwrap();
+        assert!((cuboid.half_extents.x - 1.0).abs() < 1e-5);
+        assert!((cuboid.half_extents.y - 0.5).abs() < 1e-5);
+        assert!((cuboid.half_extents.z - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn off_center_bounds_are_offset_in_a_compound() {
+        let mut app = fit_collider(AutoAABBCollider::Cuboid, Transform::from_xyz(0.0, 0.5, 0.0));
+        let compound = fitted_collider(&mut app).shape().as_compound().unwrap();
+        let (isometry, shape) = &compound.shapes()[0];
+        assert!((isometry.translation.vector.y - 0.5).abs() < 1e-5);
+        assert!((shape.as_cuboid().unwrap().half_extents.y - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn balls_wrap_the_corners_of_the_meshes() {
+        let mut app = fit_collider(AutoAABBCollider::Ball, Transform::IDENTITY);
+        let ball = fitted_collider(&mut app).shape().as_ball().unwrap();
+        assert!((ball.radius - 3.0_f32.sqrt() / 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn capsules_stand_upright_in_the_meshes() {
+        let mut app = fit_collider(
+            AutoAABBCollider::Capsule,
+            Transform::from_scale(Vec3::new(1.0, 3.0, 1.0)),
+        );
+        let capsule = fitted_collider(&mut app).shape().as_capsule().unwrap();
+        assert!((capsule.radius - 0.5).abs() < 1e-5);
+        assert!((capsule.half_height() - 1.0).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy_xpbd_3d::prelude::*;
+
+use crate::core::find_in_ancestors;
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+/// Add this to a sensor (or to one of its ancestors) to get `TriggerEntered` & `TriggerExited` events
+/// whenever something starts or stops overlapping it
+pub struct TriggerZone {
+    /// lets gameplay code tell the zones apart
+    pub id: String,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct TriggerEntered {
+    /// the entity with the `TriggerZone` component
+    pub zone: Entity,
+    pub id: String,
+    /// what entered the zone
+    pub entity: Entity,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct TriggerExited {
+    /// the entity with the `TriggerZone` component
+    pub zone: Entity,
+    pub id: String,
+    /// what left the zone
+    pub entity: Entity,
+}
+
+// the colliders are often children of the blueprint carrying the TriggerZone
+fn find_zone<'a>(
+    entity: Entity,
+    zones: &'a Query<&TriggerZone>,
+    parents: &Query<&Parent>,
+) -> Option<(Entity, &'a TriggerZone)> {
+    let zone = find_in_ancestors(entity, parents, |entity| zones.contains(entity))?;
+    zones
+        .get(zone)
+        .ok()
+        .map(|trigger_zone| (zone, trigger_zone))
+}
+
+// both entities of a collision can be zones, each gets its own event
+fn zone_events(
+    entity1: Entity,
+    entity2: Entity,
+    zones: &Query<&TriggerZone>,
+    parents: &Query<&Parent>,
+) -> Vec<(Entity, String, Entity)> {
+    [(entity1, entity2), (entity2, entity1)]
+        .into_iter()
+        .filter_map(|(zone_collider, other)| {
+            find_zone(zone_collider, zones, parents)
+                .map(|(zone, trigger_zone)| (zone, trigger_zone.id.clone(), other))
+        })
+        .collect()
+}
+
+pub fn trigger_zone_events(
+    mut collision_started_events: EventReader<CollisionStarted>,
+    mut collision_ended_events: EventReader<CollisionEnded>,
+    zones: Query<&TriggerZone>,
+    parents: Query<&Parent>,
+    mut entered_events: EventWriter<TriggerEntered>,
+    mut exited_events: EventWriter<TriggerExited>,
+) {
+    for CollisionStarted(entity1, entity2) in collision_started_events.read() {
+        for (zone, id, entity) in zone_events(*entity1, *entity2, &zones, &parents) {
+            entered_events.send(TriggerEntered { zone, id, entity });
+        }
+    }
+
+    for CollisionEnded(entity1, entity2) in collision_ended_events.read() {
+        for (zone, id, entity) in zone_events(*entity1, *entity2, &zones, &parents) {
+            exited_events.send(TriggerExited { zone, id, entity });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::ManualEventReader;
+
+    use super::*;
+
+    fn triggers_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_event::<CollisionStarted>()
+            .add_event::<CollisionEnded>()
+            .add_event::<TriggerEntered>()
+            .add_event::<TriggerExited>()
+            .add_systems(Update, trigger_zone_events);
+        app
+    }
+
+    fn read<E: Event + Clone>(app: &App, reader: &mut ManualEventReader<E>) -> Vec<E> {
+        reader
+            .read(app.world.resource::<Events<E>>())
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn zones_report_each_entry_and_exit_once() {
+        let mut app = triggers_app();
+        let zone = app
+            .world
+            .spawn(TriggerZone { id: "goal".into() })
+            .with_children(|zone| {
+                zone.spawn(Sensor);
+            })
+            .id();
+        let sensor = app.world.get::<Children>(zone).unwrap()[0];
+        let player = app.world.spawn_empty().id();
+        let wall = app.world.spawn_empty().id();
+        let mut entered = app.world.resource::<Events<TriggerEntered>>().get_reader();
+        let mut exited = app.world.resource::<Events<TriggerExited>>().get_reader();
+
+        app.world.send_event(CollisionStarted(player, sensor));
+        app.world.send_event(CollisionStarted(player, wall));
+        app.update();
+        let entries = read(&app, &mut entered);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            (entries[0].zone, entries[0].id.as_str(), entries[0].entity),
+            (zone, "goal", player)
+        );
+        assert!(read(&app, &mut exited).is_empty());
+
+        // staying inside the zone sends nothing more
+        app.update();
+        assert!(read(&app, &mut entered).is_empty());
+
+        app.world.send_event(CollisionEnded(sensor, player));
+        app.update();
+        let exits = read(&app, &mut exited);
+        assert_eq!(exits.len(), 1);
+        assert_eq!((exits[0].zone, exits[0].entity), (zone, player));
+        assert!(read(&app, &mut entered).is_empty());
+    }
+
+    #[test]
+    fn overlapping_zones_both_get_an_event() {
+        let mut app = triggers_app();
+        let zone_a = app.world.spawn(TriggerZone { id: "a".into() }).id();
+        let zone_b = app.world.spawn(TriggerZone { id: "b".into() }).id();
+        let mut entered = app.world.resource::<Events<TriggerEntered>>().get_reader();
+
+        app.world.send_event(CollisionStarted(zone_a, zone_b));
+        app.update();
+        let mut entries: Vec<_> = read(&app, &mut entered)
+            .into_iter()
+            .map(|entry| (entry.id, entry.entity))
+            .collect();
+        entries.sort();
+        assert_eq!(entries, vec![("a".into(), zone_b), ("b".into(), zone_a)]);
+    }
+}
@@ -1,6 +1,9 @@
 pub mod relationships_insert_dependant_components;
 pub use relationships_insert_dependant_components::*;
 
+pub mod relationships_find_in_ancestors;
+pub use relationships_find_in_ancestors::*;
+
 use bevy::prelude::*;
 
 pub struct EcsRelationshipsPlugin;
@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+// colliders often end up on a child of the entity we actually care about (blueprint root, enemy, etc),
+// this walks up the hierarchy (starting with the entity itself) until something matches
+pub fn find_in_ancestors(
+    entity: Entity,
+    parents: &Query<&Parent>,
+    predicate: impl Fn(Entity) -> bool,
+) -> Option<Entity> {
+    std::iter::once(entity)
+        .chain(parents.iter_ancestors(entity))
+        .find(|entity| predicate(*entity))
+}
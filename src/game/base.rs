@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::state::GameState;
+
+use super::{Enemy, PathEndReached};
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component, Default)]
+/// What the enemies are after: add it to an entity in the level, the game is over once it runs out of lives
+pub struct Base {
+    pub lives: u32,
+}
+impl Default for Base {
+    fn default() -> Self {
+        Base { lives: 20 }
+    }
+}
+
+pub fn enemies_reach_base(
+    mut path_end_events: EventReader<PathEndReached>,
+    enemies: Query<(), With<Enemy>>,
+    mut bases: Query<&mut Base>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    for PathEndReached { entity, .. } in path_end_events.read() {
+        if !enemies.contains(*entity) {
+            continue;
+        }
+        commands.entity(*entity).despawn_recursive();
+
+        let Ok(mut base) = bases.get_single_mut() else {
+            warn!(
+                "an enemy reached the end of its path, but there is no (single) Base in the level"
+            );
+            continue;
+        };
+        base.lives = base.lives.saturating_sub(1);
+        info!("an enemy reached the base, {} lives left", base.lives);
+        if base.lives == 0 {
+            next_game_state.set(GameState::InGameOver);
+        }
+    }
+}
+
+pub struct BasePlugin;
+impl Plugin for BasePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Base>().add_systems(
+            Update,
+            enemies_reach_base
+                .after(super::follow_paths)
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StatePlugin;
+
+    #[test]
+    fn enemies_take_lives_until_the_game_is_over() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin, BasePlugin))
+            .add_event::<PathEndReached>();
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        let base = app.world.spawn(Base { lives: 2 }).id();
+        app.update();
+
+        let reach_base = |app: &mut App| {
+            let enemy = app.world.spawn(Enemy).id();
+            app.world.send_event(PathEndReached {
+                entity: enemy,
+                path: "EnemyPath".into(),
+            });
+            app.update();
+            assert!(app.world.get_entity(enemy).is_none());
+        };
+
+        reach_base(&mut app);
+        assert_eq!(app.world.get::<Base>(base).unwrap().lives, 1);
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::InGame
+        );
+
+        reach_base(&mut app);
+        assert_eq!(app.world.get::<Base>(base).unwrap().lives, 0);
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<GameState>>().get(),
+            GameState::InGameOver
+        );
+    }
+
+    #[test]
+    fn bases_from_blender_start_with_lives() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin, BasePlugin));
+        let type_registry = app.world.resource::<AppTypeRegistry>().read();
+        let base = type_registry
+            .get_with_short_type_path("Base")
+            .and_then(|registration| registration.data::<ReflectDefault>())
+            .unwrap()
+            .default();
+        assert_eq!(base.downcast_ref::<Base>().unwrap().lives, 20);
+    }
+}
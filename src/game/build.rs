@@ -0,0 +1,681 @@
+use bevy::ecs::system::{Command, SystemState};
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::PrimaryWindow;
+use bevy_gltf_blueprints::{BluePrintBundle, BlueprintName, GameWorldTag, GltfBlueprintsSet};
+use bevy_xpbd_3d::prelude::{Collider, RigidBody, SpatialQuery, SpatialQueryFilter};
+use serde::Deserialize;
+
+use crate::{
+    assets::GameAssets,
+    core::{
+        auto_aabb_colliders, find_in_ancestors, physics::utils::MeshExt, physics_replace_proxies,
+        AutoAABBCollider, CollisionLayersProxy, GameLayer, MainCamera,
+    },
+    state::{AppState, GameState, StateScoped},
+};
+
+use super::{level_root, setup_towers, try_pay, Paths, Persist, Tower, Transaction};
+
+const DEFAULT_CELL_SIZE: f32 = 2.0;
+// in radians, anything steeper than this cannot be built on
+const MAX_BUILD_SLOPE: f32 = 0.35;
+// how far (horizontally) from the enemy paths towers must stay
+const PATH_CLEARANCE: f32 = 1.5;
+// how far we look for ground under the cursor
+const CURSOR_RAY_LENGTH: f32 = 500.0;
+const VALID_PREVIEW_COLOR: Color = Color::rgba(0.2, 1.0, 0.2, 0.5);
+const INVALID_PREVIEW_COLOR: Color = Color::rgba(1.0, 0.2, 0.2, 0.5);
+
+/// A tower the player can build, as described in the `*.towers.ron` files
+#[derive(Deserialize, Debug, Clone)]
+pub struct TowerDefinition {
+    /// what is shown to the player
+    pub name: String,
+    /// name of the blueprint to spawn (from `models/library`)
+    pub blueprint: String,
+    /// inserted on the spawned blueprint, whatever is left out takes its default value
+    #[serde(default)]
+    pub stats: Tower,
+    /// gold needed to build it
+    pub cost: u32,
+    /// gold needed for each upgrade, multiplied by the current level
+    pub upgrade_cost: u32,
+    /// gold given back when selling it
+    pub sell_value: u32,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct TowerCatalog {
+    pub towers: Vec<TowerDefinition>,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Tag ground (meshes) in the level with this to allow building towers on it
+pub struct BuildableGround;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStatus {
+    Free,
+    Occupied,
+    OffGrid,
+    OnPath,
+    TooSteep,
+}
+
+#[derive(Resource, Debug)]
+pub struct BuildGrid {
+    pub cell_size: f32,
+    /// cells over buildable ground
+    pub cells: HashSet<IVec2>,
+    /// cells with a tower on them
+    pub occupied: HashMap<IVec2, Entity>,
+}
+impl Default for BuildGrid {
+    fn default() -> Self {
+        BuildGrid {
+            cell_size: DEFAULT_CELL_SIZE,
+            cells: HashSet::new(),
+            occupied: HashMap::new(),
+        }
+    }
+}
+
+impl BuildGrid {
+    pub fn cell_at(&self, position: Vec3) -> IVec2 {
+        IVec2::new(
+            (position.x / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// horizontal center of a cell, at height 0
+    pub fn cell_center(&self, cell: IVec2) -> Vec3 {
+        Vec3::new(
+            (cell.x as f32 + 0.5) * self.cell_size,
+            0.0,
+            (cell.y as f32 + 0.5) * self.cell_size,
+        )
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct BuildMode {
+    /// index of the selected tower in the tower catalog, None when not building
+    pub selected: Option<usize>,
+    /// the cell under the cursor, with the ground position at its center & whether it can be built on
+    pub hovered: Option<(IVec2, Vec3, CellStatus)>,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Added to the towers built by the player
+pub struct PlacedTower {
+    pub cell: IVec2,
+    /// index of the tower in the tower catalog
+    pub definition: usize,
+    pub level: u32,
+}
+
+#[derive(Component, Debug)]
+/// The ghost of the selected tower, following the cursor
+pub struct BuildPreview {
+    pub selected: usize,
+}
+
+#[derive(Component, Debug)]
+/// Marks meshes of the build preview that got their own (tintable) copy of their material
+pub struct BuildPreviewMaterial;
+
+/// Spawns a tower blueprint on a cell of the build grid & marks the cell as occupied, if the player can afford it
+pub struct PlaceTower {
+    /// index of the tower in the tower catalog
+    pub definition: usize,
+    pub blueprint: String,
+    pub stats: Tower,
+    pub cost: u32,
+    pub cell: IVec2,
+    pub position: Vec3,
+}
+
+impl Command for PlaceTower {
+    fn apply(self, world: &mut World) {
+        if world
+            .resource::<BuildGrid>()
+            .occupied
+            .contains_key(&self.cell)
+        {
+            warn!(
+                "cannot place {}, cell {} is occupied",
+                self.blueprint, self.cell
+            );
+            return;
+        }
+        let mut game_worlds = SystemState::<Query<&Children, With<GameWorldTag>>>::new(world);
+        let Some(level_root) = level_root(&game_worlds.get(world)) else {
+            warn!("cannot place {}, there is no level", self.blueprint);
+            return;
+        };
+        if !try_pay(world, Transaction::Build, self.cost) {
+            return;
+        }
+
+        let tower = world
+            .spawn((
+                BluePrintBundle {
+                    blueprint: BlueprintName(self.blueprint.clone()),
+                    transform: TransformBundle::from_transform(Transform::from_translation(
+                        self.position,
+                    )),
+                    ..Default::default()
+                },
+                Name::from(format!(
+                    "{}_{}_{}",
+                    self.blueprint, self.cell.x, self.cell.y
+                )),
+                PlacedTower {
+                    cell: self.cell,
+                    definition: self.definition,
+                    level: 1,
+                },
+                self.stats,
+                CollisionLayersProxy(vec![GameLayer::Tower]),
+                Persist,
+            ))
+            .id();
+        world.entity_mut(level_root).add_child(tower);
+        world
+            .resource_mut::<BuildGrid>()
+            .occupied
+            .insert(self.cell, tower);
+        info!("placed {} on cell {}", self.blueprint, self.cell);
+    }
+}
+
+// horizontal distance from a point to a segment
+fn distance_to_segment(point: Vec3, a: Vec3, b: Vec3) -> f32 {
+    let (point, a, b) = (point.xz(), a.xz(), b.xz());
+    let segment = b - a;
+    let t = if segment.length_squared() > 0.0 {
+        ((point - a).dot(segment) / segment.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + segment * t)
+}
+
+pub fn cell_status(
+    grid: &BuildGrid,
+    paths: &Paths,
+    cell: IVec2,
+    ground_normal: Vec3,
+) -> CellStatus {
+    if !grid.cells.contains(&cell) {
+        return CellStatus::OffGrid;
+    }
+    if grid.occupied.contains_key(&cell) {
+        return CellStatus::Occupied;
+    }
+    let center = grid.cell_center(cell);
+    let on_path = paths.values().any(|waypoints| {
+        waypoints
+            .windows(2)
+            .any(|segment| distance_to_segment(center, segment[0], segment[1]) < PATH_CLEARANCE)
+    });
+    if on_path {
+        return CellStatus::OnPath;
+    }
+    if ground_normal.angle_between(Vec3::Y) > MAX_BUILD_SLOPE {
+        return CellStatus::TooSteep;
+    }
+    CellStatus::Free
+}
+
+// rebuilds the grid cells from the (world space) bounds of the buildable ground meshes
+pub fn build_grid_from_ground(
+    mut grid: ResMut<BuildGrid>,
+    grounds: Query<Entity, With<BuildableGround>>,
+    changed_grounds: Query<
+        (),
+        (
+            With<BuildableGround>,
+            Or<(Added<BuildableGround>, Changed<GlobalTransform>)>,
+        ),
+    >,
+    mut removed_grounds: RemovedComponents<BuildableGround>,
+    meshes: Res<Assets<Mesh>>,
+    mesh_handles: Query<&Handle<Mesh>>,
+    children: Query<&Children>,
+    transforms: Query<&GlobalTransform>,
+) {
+    let removed = removed_grounds.read().count();
+    if changed_grounds.is_empty() && removed == 0 {
+        return;
+    }
+
+    let cell_size = grid.cell_size;
+    grid.cells.clear();
+    for ground in grounds.iter() {
+        let mut min = Vec2::splat(f32::MAX);
+        let mut max = Vec2::splat(f32::MIN);
+        for (mesh_entity, mesh) in
+            Mesh::search_in_children(ground, &children, &meshes, &mesh_handles)
+        {
+            let Ok(transform) = transforms.get(mesh_entity) else {
+                continue;
+            };
+            let Some(positions) = mesh
+                .attribute(Mesh::ATTRIBUTE_POSITION)
+                .and_then(|positions| positions.as_float3())
+            else {
+                continue;
+            };
+            for position in positions {
+                let position = transform.transform_point(Vec3::from(*position)).xz();
+                min = min.min(position);
+                max = max.max(position);
+            }
+        }
+        if min.x > max.x {
+            continue;
+        }
+
+        let first = (min / cell_size).floor().as_ivec2();
+        let last = (max / cell_size).ceil().as_ivec2();
+        for x in first.x..last.x {
+            for y in first.y..last.y {
+                let center = (Vec2::new(x as f32, y as f32) + 0.5) * cell_size;
+                if center.cmpge(min).all() && center.cmple(max).all() {
+                    grid.cells.insert(IVec2::new(x, y));
+                }
+            }
+        }
+    }
+    info!("build grid has {} cells", grid.cells.len());
+}
+
+// towers that are part of the level itself also occupy their cell (once their transform is known),
+// and sold/destroyed ones free theirs. Placed towers are added by PlaceTower
+pub fn sync_occupied_cells(
+    mut grid: ResMut<BuildGrid>,
+    level_towers: Query<
+        (Entity, &GlobalTransform),
+        (With<Tower>, Without<PlacedTower>, Changed<GlobalTransform>),
+    >,
+    mut removed_towers: RemovedComponents<Tower>,
+) {
+    let removed: HashSet<Entity> = removed_towers.read().collect();
+    if !removed.is_empty() {
+        grid.occupied.retain(|_, tower| !removed.contains(tower));
+    }
+    for (entity, transform) in level_towers.iter() {
+        let cell = grid.cell_at(transform.translation());
+        if grid.occupied.get(&cell) == Some(&entity) {
+            continue;
+        }
+        grid.occupied.retain(|_, tower| *tower != entity);
+        grid.occupied.insert(cell, entity);
+    }
+}
+
+pub fn build_mode_input(
+    keycode: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    game_assets: Res<GameAssets>,
+    catalogs: Res<Assets<TowerCatalog>>,
+    mut build_mode: ResMut<BuildMode>,
+    mut commands: Commands,
+) {
+    let Some(catalog) = catalogs.get(&game_assets.towers) else {
+        return;
+    };
+
+    const SELECTION_KEYS: [KeyCode; 9] = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    for (index, key) in SELECTION_KEYS.iter().enumerate() {
+        if keycode.just_pressed(*key) && index < catalog.towers.len() {
+            // pressing the key of the selected tower again leaves build mode
+            build_mode.selected = if build_mode.selected == Some(index) {
+                None
+            } else {
+                info!("building {}", catalog.towers[index].name);
+                Some(index)
+            };
+        }
+    }
+    if mouse_buttons.just_pressed(MouseButton::Right) {
+        build_mode.selected = None;
+    }
+
+    let Some(selected) = build_mode.selected else {
+        return;
+    };
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        if let Some((cell, position, CellStatus::Free)) = build_mode.hovered {
+            commands.add(PlaceTower {
+                definition: selected,
+                blueprint: catalog.towers[selected].blueprint.clone(),
+                stats: catalog.towers[selected].stats.clone(),
+                cost: catalog.towers[selected].cost,
+                cell,
+                position,
+            });
+        }
+    }
+}
+
+pub fn update_build_cursor(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    spatial_query: SpatialQuery,
+    grid: Res<BuildGrid>,
+    paths: Res<Paths>,
+    mut build_mode: ResMut<BuildMode>,
+) {
+    // also done when not building, to manage existing towers
+    build_mode.hovered = None;
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(ray) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world(camera_transform, cursor))
+    else {
+        return;
+    };
+
+    // only the ground is looked for: towers, enemies & the player are on their own layers & do not block the cursor
+    let filter = || SpatialQueryFilter::new().with_masks([GameLayer::Terrain]);
+    let Some(hit) =
+        spatial_query.cast_ray(ray.origin, ray.direction, CURSOR_RAY_LENGTH, true, filter())
+    else {
+        return;
+    };
+    let hit_point = ray.origin + ray.direction * hit.time_of_impact;
+    let cell = grid.cell_at(hit_point);
+
+    // the ground at the center of the cell decides where the tower goes & how steep it is,
+    // looked for from a bit above the hit, in case the ground rises towards the center
+    let center = grid.cell_center(cell);
+    let above = Vec3::new(center.x, hit_point.y + grid.cell_size, center.z);
+    let Some(ground) =
+        spatial_query.cast_ray(above, Vec3::NEG_Y, CURSOR_RAY_LENGTH, true, filter())
+    else {
+        return;
+    };
+    let position = above + Vec3::NEG_Y * ground.time_of_impact;
+    let status = cell_status(&grid, &paths, cell, ground.normal);
+    build_mode.hovered = Some((cell, position, status));
+}
+
+pub fn update_build_preview(
+    build_mode: Res<BuildMode>,
+    game_assets: Res<GameAssets>,
+    catalogs: Res<Assets<TowerCatalog>>,
+    mut previews: Query<(Entity, &BuildPreview, &mut Transform, &mut Visibility)>,
+    mut commands: Commands,
+) {
+    // despawn the preview if we stopped building or picked another tower
+    for (entity, preview, _, _) in previews.iter() {
+        if build_mode.selected != Some(preview.selected) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let Some(selected) = build_mode.selected else {
+        return;
+    };
+    let Some(definition) = catalogs
+        .get(&game_assets.towers)
+        .and_then(|catalog| catalog.towers.get(selected))
+    else {
+        return;
+    };
+
+    match previews
+        .iter_mut()
+        .find(|(_, preview, _, _)| preview.selected == selected)
+    {
+        Some((_, _, mut transform, mut visibility)) => match build_mode.hovered {
+            Some((_, position, _)) => {
+                transform.translation = position;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        },
+        None => {
+            commands.spawn((
+                BluePrintBundle {
+                    blueprint: BlueprintName(definition.blueprint.clone()),
+                    ..Default::default()
+                },
+                Name::from(format!("{}_preview", definition.blueprint)),
+                BuildPreview { selected },
+                StateScoped(AppState::AppRunning),
+            ));
+        }
+    }
+}
+
+// the preview is just a ghost: it should not collide, fire at enemies, or share its materials with real towers.
+// This runs as soon as the blueprint's components are there, before they turn into colliders & towers
+#[allow(clippy::too_many_arguments)]
+pub fn sanitize_build_preview(
+    previews: Query<(), With<BuildPreview>>,
+    parents: Query<&Parent>,
+    added_towers: Query<Entity, Added<Tower>>,
+    added_bodies: Query<Entity, Added<RigidBody>>,
+    added_collider_proxies: Query<
+        Entity,
+        Or<(
+            Added<physics_replace_proxies::Collider>,
+            Added<AutoAABBCollider>,
+            Added<Collider>,
+        )>,
+    >,
+    added_materials: Query<
+        (Entity, &Handle<StandardMaterial>),
+        (
+            Added<Handle<StandardMaterial>>,
+            Without<BuildPreviewMaterial>,
+        ),
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    let in_preview = |entity: Entity| {
+        find_in_ancestors(entity, &parents, |entity| previews.contains(entity)).is_some()
+    };
+
+    for entity in added_towers.iter().filter(|entity| in_preview(*entity)) {
+        commands.entity(entity).remove::<Tower>();
+    }
+    for entity in added_bodies.iter().filter(|entity| in_preview(*entity)) {
+        commands.entity(entity).remove::<RigidBody>();
+    }
+    for entity in added_collider_proxies
+        .iter()
+        .filter(|entity| in_preview(*entity))
+    {
+        commands.entity(entity).remove::<(
+            physics_replace_proxies::Collider,
+            AutoAABBCollider,
+            Collider,
+        )>();
+    }
+    for (entity, material) in added_materials.iter() {
+        if !in_preview(entity) {
+            continue;
+        }
+        let Some(mut material) = materials.get(material).cloned() else {
+            continue;
+        };
+        material.alpha_mode = AlphaMode::Blend;
+        commands
+            .entity(entity)
+            .insert((materials.add(material), BuildPreviewMaterial));
+    }
+}
+
+pub fn tint_build_preview(
+    build_mode: Res<BuildMode>,
+    preview_materials: Query<&Handle<StandardMaterial>, With<BuildPreviewMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !build_mode.is_changed() {
+        return;
+    }
+    let color = match build_mode.hovered {
+        Some((_, _, CellStatus::Free)) => VALID_PREVIEW_COLOR,
+        _ => INVALID_PREVIEW_COLOR,
+    };
+    for handle in preview_materials.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = color;
+        }
+    }
+}
+
+pub struct BuildPlugin;
+impl Plugin for BuildPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<BuildableGround>()
+            .register_type::<PlacedTower>()
+            .init_resource::<BuildGrid>()
+            .init_resource::<BuildMode>()
+            .add_systems(
+                Update,
+                (
+                    build_grid_from_ground.after(GltfBlueprintsSet::AfterSpawn),
+                    sanitize_build_preview
+                        .after(GltfBlueprintsSet::AfterSpawn)
+                        .before(physics_replace_proxies)
+                        .before(auto_aabb_colliders)
+                        .before(setup_towers),
+                    // not only while in game, so no tower removal is missed
+                    sync_occupied_cells.before(update_build_cursor),
+                    (
+                        build_mode_input,
+                        update_build_cursor,
+                        update_build_preview,
+                        tint_build_preview,
+                    )
+                        .chain()
+                        .run_if(in_state(GameState::InGame)),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{TowerCooldown, Wallet};
+
+    #[test]
+    fn build_previews_never_become_towers_or_colliders() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .add_systems(
+                Update,
+                (
+                    sanitize_build_preview
+                        .before(physics_replace_proxies)
+                        .before(setup_towers),
+                    physics_replace_proxies,
+                    setup_towers,
+                ),
+            );
+        // what the blueprint of a tower brings along
+        let preview = app
+            .world
+            .spawn((
+                BuildPreview { selected: 0 },
+                Tower::default(),
+                RigidBody::Static,
+                SpatialBundle::default(),
+            ))
+            .with_children(|preview| {
+                preview.spawn((
+                    Name::new("tower_collider"),
+                    physics_replace_proxies::Collider::Ball(1.0),
+                    SpatialBundle::default(),
+                ));
+            })
+            .id();
+
+        for _ in 0..3 {
+            app.update();
+        }
+        let preview = app.world.entity(preview);
+        assert!(!preview.contains::<Tower>());
+        assert!(!preview.contains::<TowerCooldown>());
+        assert!(!preview.contains::<RigidBody>());
+        assert_eq!(
+            app.world
+                .query_filtered::<(), With<Collider>>()
+                .iter(&app.world)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn placing_a_tower_without_a_level_does_nothing() {
+        let mut world = World::new();
+        world.init_resource::<BuildGrid>();
+        world.insert_resource(Wallet { gold: 100 });
+        PlaceTower {
+            definition: 0,
+            blueprint: "Pillar".into(),
+            stats: Tower::default(),
+            cost: 50,
+            cell: IVec2::ZERO,
+            position: Vec3::ZERO,
+        }
+        .apply(&mut world);
+        assert_eq!(world.resource::<Wallet>().gold, 100);
+        assert!(world.resource::<BuildGrid>().occupied.is_empty());
+    }
+
+    #[test]
+    fn level_towers_occupy_their_cell_until_removed() {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<BuildGrid>()
+            .add_systems(Update, sync_occupied_cells);
+        let tower = app
+            .world
+            .spawn((
+                Tower::default(),
+                GlobalTransform::from_translation(Vec3::new(3.0, 0.0, 5.0)),
+            ))
+            .id();
+        app.update();
+        let cell = app
+            .world
+            .resource::<BuildGrid>()
+            .cell_at(Vec3::new(3.0, 0.0, 5.0));
+        assert_eq!(
+            app.world.resource::<BuildGrid>().occupied.get(&cell),
+            Some(&tower)
+        );
+
+        app.world.despawn(tower);
+        app.update();
+        assert!(app.world.resource::<BuildGrid>().occupied.is_empty());
+    }
+}
@@ -9,14 +9,14 @@ use super::CharacterControllerBundle;
 
 #[derive(Component, Reflect, Default, Debug)]
 #[reflect(Component)]
-/// Demo marker component
-pub struct Player;
+/// Demo marker component, for the xpbd character controller (the game uses the tnua one, see `plugin_player::Player`)
+pub struct ControllerPlayer;
 
 
 
 pub fn setup_player_controller(
     mut commands: Commands,
-    mut q_player: Query<(Entity, &mut Transform), Added<Player>>
+    mut q_player: Query<(Entity, &mut Transform), Added<ControllerPlayer>>
 ) {
     for (entity, mut transform) in q_player.iter_mut(){
         commands.entity(entity)
@@ -43,7 +43,7 @@ pub fn setup_player_controller(
 
 pub fn setup_player_controller2(
     mut commands: Commands,
-    mut q_player: Query<(Entity, &mut Transform), Added<Player>>
+    mut q_player: Query<(Entity, &mut Transform), Added<ControllerPlayer>>
 ) {
     for (entity, mut transform) in q_player.iter_mut(){
         commands.entity(entity)
@@ -65,7 +65,7 @@ pub fn setup_player_controller2(
 
 pub fn character_movement(
     keyboard_input: Res<Input<KeyCode>>,
-    mut players: Query<(&mut LinearVelocity, &ShapeHits), With<Player>>,
+    mut players: Query<(&mut LinearVelocity, &ShapeHits), With<ControllerPlayer>>,
 ) {
     for (mut linear_velocity, ground_hits) in &mut players {
         // Reset vertical valocity if grounded, otherwise apply gravity
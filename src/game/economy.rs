@@ -0,0 +1,383 @@
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+
+use crate::{
+    assets::{CoreAssets, GameAssets},
+    state::{AppState, GameState},
+};
+
+use super::{
+    current_level_definition, BuildMode, CellStatus, CurrentLevel, Died, Enemy, LevelCatalog,
+    PlacedTower, Tower, TowerCatalog, WaveCleared, WavesDefinition,
+};
+
+// gold for killing an enemy without a Bounty component
+const DEFAULT_BOUNTY: u32 = 5;
+// how much better each upgrade makes a tower
+const UPGRADE_DAMAGE_FACTOR: f32 = 1.25;
+const UPGRADE_RANGE_FACTOR: f32 = 1.1;
+
+#[derive(Resource, Debug)]
+pub struct EconomySettings {
+    pub starting_gold: u32,
+    /// fraction of the current gold earned at the end of each wave, None to disable interest
+    pub interest_rate: Option<f32>,
+    /// the most interest that can be earned in one wave
+    pub interest_cap: u32,
+}
+impl Default for EconomySettings {
+    fn default() -> Self {
+        EconomySettings {
+            starting_gold: 100,
+            interest_rate: None,
+            interest_cap: 50,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Wallet {
+    pub gold: u32,
+}
+
+impl Wallet {
+    pub fn can_afford(&self, cost: u32) -> bool {
+        self.gold >= cost
+    }
+
+    /// takes the gold out of the wallet, if there is enough of it
+    pub fn try_spend(&mut self, cost: u32) -> bool {
+        if !self.can_afford(cost) {
+            return false;
+        }
+        self.gold -= cost;
+        true
+    }
+
+    pub fn earn(&mut self, amount: u32) {
+        self.gold += amount;
+    }
+}
+
+#[derive(Component, Reflect, Debug, Deref, DerefMut)]
+#[reflect(Component)]
+/// Gold earned when killing this enemy
+pub struct Bounty(pub u32);
+impl Default for Bounty {
+    fn default() -> Self {
+        Bounty(DEFAULT_BOUNTY)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transaction {
+    Build,
+    Upgrade,
+}
+
+#[derive(Event, Debug)]
+pub struct TransactionFailed {
+    pub transaction: Transaction,
+    pub cost: u32,
+    pub available: u32,
+}
+
+/// Spends gold from the wallet, sending a `TransactionFailed` event if there is not enough
+pub fn try_pay(world: &mut World, transaction: Transaction, cost: u32) -> bool {
+    let mut wallet = world.resource_mut::<Wallet>();
+    if wallet.try_spend(cost) {
+        return true;
+    }
+    let available = wallet.gold;
+    warn!(
+        "cannot afford {:?}: costs {}, only {} gold",
+        transaction, cost, available
+    );
+    world.send_event(TransactionFailed {
+        transaction,
+        cost,
+        available,
+    });
+    false
+}
+
+/// Upgrades a placed tower, if the player can afford it
+pub struct UpgradeTower {
+    pub tower: Entity,
+}
+
+impl Command for UpgradeTower {
+    fn apply(self, world: &mut World) {
+        let Some(placed) = world.get::<PlacedTower>(self.tower) else {
+            return;
+        };
+        let (definition, level) = (placed.definition, placed.level);
+        let Some(upgrade_cost) = tower_definition_cost(world, definition, |definition| {
+            definition.upgrade_cost * level
+        }) else {
+            return;
+        };
+        if !try_pay(world, Transaction::Upgrade, upgrade_cost) {
+            return;
+        }
+
+        let mut tower = world.entity_mut(self.tower);
+        if let Some(mut placed) = tower.get_mut::<PlacedTower>() {
+            placed.level += 1;
+        }
+        if let Some(mut settings) = tower.get_mut::<Tower>() {
+            settings.damage *= UPGRADE_DAMAGE_FACTOR;
+            settings.range *= UPGRADE_RANGE_FACTOR;
+        }
+        info!("upgraded tower {:?} to level {}", self.tower, level + 1);
+    }
+}
+
+/// Sells a placed tower, freeing its cell & refunding part of its cost
+pub struct SellTower {
+    pub tower: Entity,
+}
+
+impl Command for SellTower {
+    fn apply(self, world: &mut World) {
+        let Some(placed) = world.get::<PlacedTower>(self.tower) else {
+            return;
+        };
+        let Some(refund) =
+            tower_definition_cost(world, placed.definition, |definition| definition.sell_value)
+        else {
+            return;
+        };
+        world.resource_mut::<Wallet>().earn(refund);
+        world.entity_mut(self.tower).despawn_recursive();
+        info!("sold tower {:?} for {} gold", self.tower, refund);
+    }
+}
+
+fn tower_definition_cost(
+    world: &World,
+    definition: usize,
+    cost: impl Fn(&super::TowerDefinition) -> u32,
+) -> Option<u32> {
+    let game_assets = world.get_resource::<GameAssets>()?;
+    world
+        .resource::<Assets<TowerCatalog>>()
+        .get(&game_assets.towers)
+        .and_then(|catalog| catalog.towers.get(definition))
+        .map(cost)
+}
+
+pub fn reset_wallet(
+    mut commands: Commands,
+    settings: Res<EconomySettings>,
+    core_assets: Res<CoreAssets>,
+    level_catalogs: Res<Assets<LevelCatalog>>,
+    current_level: Res<CurrentLevel>,
+) {
+    let starting_gold = current_level_definition(&core_assets, &level_catalogs, &current_level)
+        .and_then(|level| level.starting_gold)
+        .unwrap_or(settings.starting_gold);
+    commands.insert_resource(Wallet {
+        gold: starting_gold,
+    });
+}
+
+pub fn earn_bounties(
+    mut died_events: EventReader<Died>,
+    enemies: Query<Option<&Bounty>, With<Enemy>>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for Died { entity, .. } in died_events.read() {
+        if let Ok(bounty) = enemies.get(*entity) {
+            wallet.earn(bounty.map_or(DEFAULT_BOUNTY, |bounty| bounty.0));
+        }
+    }
+}
+
+pub fn earn_wave_rewards(
+    mut wave_cleared_events: EventReader<WaveCleared>,
+    game_assets: Res<GameAssets>,
+    waves_definitions: Res<Assets<WavesDefinition>>,
+    settings: Res<EconomySettings>,
+    mut wallet: ResMut<Wallet>,
+) {
+    for WaveCleared { index } in wave_cleared_events.read() {
+        // interest is computed on what the player saved up, before the reward
+        if let Some(rate) = settings.interest_rate {
+            let interest = ((wallet.gold as f32 * rate) as u32).min(settings.interest_cap);
+            wallet.earn(interest);
+        }
+        let reward = waves_definitions
+            .get(&game_assets.waves)
+            .and_then(|definition| definition.waves.get(*index))
+            .map_or(0, |wave| wave.reward);
+        wallet.earn(reward);
+        info!("wave {} cleared, now at {} gold", index, wallet.gold);
+    }
+}
+
+// upgrade (U) or sell (X) the tower under the cursor, when not building
+pub fn tower_management_input(
+    keycode: Res<Input<KeyCode>>,
+    build_mode: Res<BuildMode>,
+    placed_towers: Query<(Entity, &PlacedTower)>,
+    mut commands: Commands,
+) {
+    if build_mode.selected.is_some() {
+        return;
+    }
+    let Some((cell, _, CellStatus::Occupied)) = build_mode.hovered else {
+        return;
+    };
+    let Some((tower, _)) = placed_towers.iter().find(|(_, placed)| placed.cell == cell) else {
+        return;
+    };
+
+    if keycode.just_pressed(KeyCode::U) {
+        commands.add(UpgradeTower { tower });
+    } else if keycode.just_pressed(KeyCode::X) {
+        commands.add(SellTower { tower });
+    }
+}
+
+pub struct EconomyPlugin;
+impl Plugin for EconomyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Bounty>()
+            .init_resource::<EconomySettings>()
+            .init_resource::<Wallet>()
+            .add_event::<TransactionFailed>()
+            .add_systems(OnEnter(AppState::AppRunning), reset_wallet)
+            .add_systems(
+                Update,
+                (
+                    earn_bounties.after(super::apply_damage),
+                    earn_wave_rewards,
+                    tower_management_input.after(super::update_build_cursor),
+                )
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn economy_app(gold: u32) -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<TowerCatalog>()
+            .init_asset::<WavesDefinition>()
+            .add_event::<TransactionFailed>()
+            .add_event::<WaveCleared>()
+            .init_resource::<EconomySettings>()
+            .insert_resource(Wallet { gold });
+
+        let towers = app.world.resource_mut::<Assets<TowerCatalog>>().add(
+            ron::from_str::<TowerCatalog>(
+                r#"(towers: [(name: "Arrow", blueprint: "Tower_Arrow", cost: 50, upgrade_cost: 40,
+                    sell_value: 25)])"#,
+            )
+            .unwrap(),
+        );
+        let waves = app.world.resource_mut::<Assets<WavesDefinition>>().add(
+            ron::from_str::<WavesDefinition>(
+                r#"(waves: [(enemy: "Fox", count: 3, interval: 1.0, spawn_point: "EnemySpawn",
+                    path: "EnemyPath", delay: 2.0, speed: 1.0, health: 10.0, reward: 20)])"#,
+            )
+            .unwrap(),
+        );
+        app.insert_resource(GameAssets {
+            world: Handle::default(),
+            models: default(),
+            waves,
+            towers,
+            materials: Handle::default(),
+            sounds: Handle::default(),
+        });
+        app
+    }
+
+    fn place_tower(app: &mut App) -> Entity {
+        app.world
+            .spawn((
+                PlacedTower {
+                    cell: IVec2::ZERO,
+                    definition: 0,
+                    level: 1,
+                },
+                Tower::default(),
+            ))
+            .id()
+    }
+
+    fn failed_transactions(app: &App) -> Vec<(Transaction, u32, u32)> {
+        let events = app.world.resource::<Events<TransactionFailed>>();
+        events
+            .get_reader()
+            .read(events)
+            .map(|failed| (failed.transaction, failed.cost, failed.available))
+            .collect()
+    }
+
+    #[test]
+    fn upgrades_cost_more_at_each_level_and_fail_without_gold() {
+        let mut app = economy_app(30);
+        let tower = place_tower(&mut app);
+
+        UpgradeTower { tower }.apply(&mut app.world);
+        assert_eq!(
+            failed_transactions(&app),
+            vec![(Transaction::Upgrade, 40, 30)]
+        );
+        assert_eq!(app.world.get::<PlacedTower>(tower).unwrap().level, 1);
+        assert_eq!(app.world.resource::<Wallet>().gold, 30);
+
+        app.world.resource_mut::<Wallet>().earn(70);
+        UpgradeTower { tower }.apply(&mut app.world);
+        assert_eq!(app.world.get::<PlacedTower>(tower).unwrap().level, 2);
+        assert_eq!(
+            app.world.get::<Tower>(tower).unwrap().damage,
+            Tower::default().damage * UPGRADE_DAMAGE_FACTOR
+        );
+        assert_eq!(app.world.resource::<Wallet>().gold, 60);
+
+        // the second upgrade costs twice as much
+        UpgradeTower { tower }.apply(&mut app.world);
+        assert_eq!(
+            failed_transactions(&app).last(),
+            Some(&(Transaction::Upgrade, 80, 60))
+        );
+        assert_eq!(app.world.get::<PlacedTower>(tower).unwrap().level, 2);
+    }
+
+    #[test]
+    fn selling_refunds_the_tower() {
+        let mut app = economy_app(0);
+        let tower = place_tower(&mut app);
+        SellTower { tower }.apply(&mut app.world);
+        assert_eq!(app.world.resource::<Wallet>().gold, 25);
+        assert!(app.world.get_entity(tower).is_none());
+    }
+
+    #[test]
+    fn cleared_waves_pay_their_reward_plus_capped_interest() {
+        let mut app = economy_app(30);
+        app.insert_resource(EconomySettings {
+            interest_rate: Some(0.1),
+            interest_cap: 5,
+            ..default()
+        })
+        .add_systems(Update, earn_wave_rewards);
+
+        app.world.send_event(WaveCleared { index: 0 });
+        app.update();
+        assert_eq!(app.world.resource::<Wallet>().gold, 30 + 3 + 20);
+
+        app.insert_resource(Wallet { gold: 200 });
+        app.world.send_event(WaveCleared { index: 0 });
+        app.update();
+        assert_eq!(app.world.resource::<Wallet>().gold, 200 + 5 + 20);
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use bevy_gltf_blueprints::{AnimationPlayerLink, Animations, GltfBlueprintsSet};
+use serde::Deserialize;
+
+use crate::state::GameState;
+
+use super::PathFollower;
+
+// names of the animations played (if present) before despawning something that died
+const DEATH_ANIMATIONS: [&str; 2] = ["Death", "Die"];
+
+#[derive(Reflect, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DamageKind {
+    #[default]
+    Physical,
+    Fire,
+    Ice,
+    Magic,
+    Poison,
+}
+
+#[derive(Component, Reflect, Debug, Deref, DerefMut)]
+#[reflect(Component)]
+/// Current health, anything with this component can be damaged & killed
+pub struct Health(pub f32);
+impl Default for Health {
+    fn default() -> Self {
+        Health(100.0)
+    }
+}
+
+#[derive(Component, Reflect, Debug, Deref, DerefMut)]
+#[reflect(Component)]
+/// Upper limit for healing, defaults to the starting health if not authored
+pub struct MaxHealth(pub f32);
+impl Default for MaxHealth {
+    fn default() -> Self {
+        MaxHealth(100.0)
+    }
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Fraction of the damage of each kind that gets ignored: 0.0 takes full damage, 1.0 is immune
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub ice: f32,
+    pub magic: f32,
+    pub poison: f32,
+}
+
+impl Resistances {
+    pub fn get(&self, kind: DamageKind) -> f32 {
+        match kind {
+            DamageKind::Physical => self.physical,
+            DamageKind::Fire => self.fire,
+            DamageKind::Ice => self.ice,
+            DamageKind::Magic => self.magic,
+            DamageKind::Poison => self.poison,
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+    pub source: Option<Entity>,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct Died {
+    pub entity: Entity,
+    /// whatever dealt the killing blow
+    pub source: Option<Entity>,
+}
+
+#[derive(Component, Debug, Deref, DerefMut)]
+/// Added to entities playing their death animation, they get despawned once it is over
+pub struct Dying(pub Timer);
+
+pub fn setup_max_health(
+    mut commands: Commands,
+    added_healths: Query<(Entity, &Health), (Added<Health>, Without<MaxHealth>)>,
+) {
+    for (entity, health) in added_healths.iter() {
+        commands.entity(entity).insert(MaxHealth(health.0));
+    }
+}
+
+pub fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut targets: Query<(&mut Health, Option<&Resistances>), Without<Dying>>,
+    mut died_events: EventWriter<Died>,
+) {
+    for event in damage_events.read() {
+        let Ok((mut health, resistances)) = targets.get_mut(event.target) else {
+            continue;
+        };
+        // already killed by an earlier event this frame
+        if health.0 <= 0.0 {
+            continue;
+        }
+
+        let resistance = resistances.map_or(0.0, |resistances| resistances.get(event.kind));
+        health.0 -= event.amount * (1.0 - resistance).max(0.0);
+        if health.0 <= 0.0 {
+            health.0 = 0.0;
+            died_events.send(Died {
+                entity: event.target,
+                source: event.source,
+            });
+        }
+    }
+}
+
+pub fn handle_deaths(
+    mut died_events: EventReader<Died>,
+    animated: Query<(&AnimationPlayerLink, &Animations)>,
+    mut animation_players: Query<&mut AnimationPlayer>,
+    animation_clips: Res<Assets<AnimationClip>>,
+    mut commands: Commands,
+) {
+    for Died { entity, .. } in died_events.read() {
+        let Some(mut entity_commands) = commands.get_entity(*entity) else {
+            continue;
+        };
+
+        let death_animation = animated.get(*entity).ok().and_then(|(link, animations)| {
+            DEATH_ANIMATIONS
+                .iter()
+                .find_map(|name| animations.named_animations.get(*name))
+                .map(|animation| (link, animation))
+        });
+
+        match death_animation {
+            Some((link, animation)) => {
+                let duration = animation_clips
+                    .get(animation)
+                    .map_or(0.0, |clip| clip.duration());
+                if let Ok(mut animation_player) = animation_players.get_mut(link.0) {
+                    animation_player.play(animation.clone());
+                }
+                // the dead don't walk
+                entity_commands
+                    .insert(Dying(Timer::from_seconds(duration, TimerMode::Once)))
+                    .remove::<PathFollower>();
+            }
+            None => entity_commands.despawn_recursive(),
+        }
+    }
+}
+
+pub fn despawn_dying(
+    time: Res<Time>,
+    mut dying: Query<(Entity, &mut Dying)>,
+    mut commands: Commands,
+) {
+    for (entity, mut timer) in dying.iter_mut() {
+        if timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub struct HealthPlugin;
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Health>()
+            .register_type::<MaxHealth>()
+            .register_type::<Resistances>()
+            .register_type::<DamageKind>()
+            .add_event::<DamageEvent>()
+            .add_event::<Died>()
+            .add_systems(
+                Update,
+                (
+                    setup_max_health.after(GltfBlueprintsSet::AfterSpawn),
+                    (apply_damage, handle_deaths, despawn_dying)
+                        .chain()
+                        .run_if(in_state(GameState::InGame)),
+                ),
+            );
+    }
+}
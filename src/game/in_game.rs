@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 
 use crate::{
-    assets::GameAssets,
-    state::{GameState, InAppRunning},
+    assets::{CoreAssets, GameAssets, LoadingError},
+    state::{AppState, GameState, StateScoped},
 };
 use bevy_gltf_blueprints::{BluePrintBundle, BlueprintName, GameWorldTag};
 
@@ -11,10 +11,18 @@ use bevy_xpbd_3d::prelude::*;
 
 use rand::Rng;
 
+use super::{
+    current_level_definition, level_root, CurrentLevel, LevelCatalog, HEALTH_PICKUP_BLUEPRINT,
+};
+
 pub fn setup_game(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
+    core_assets: Res<CoreAssets>,
+    level_catalogs: Res<Assets<LevelCatalog>>,
+    current_level: Res<CurrentLevel>,
     models: Res<Assets<bevy::gltf::Gltf>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
     println!("setting up all stuff");
@@ -23,20 +31,30 @@ pub fn setup_game(
         brightness: 0.2,
     });
     // here we actually spawn our game world/level
+    let scene_index = current_level_definition(&core_assets, &level_catalogs, &current_level)
+        .map_or(0, |level| level.scene);
+    let Some(scene) = models
+        .get(game_assets.world.id())
+        .and_then(|world| world.scenes.get(scene_index))
+    else {
+        commands.insert_resource(LoadingError(format!(
+            "the level has no scene {}",
+            scene_index
+        )));
+        next_app_state.set(AppState::LoadingFailed);
+        return;
+    };
 
     commands.spawn((
         SceneBundle {
             // note: because of this issue https://github.com/bevyengine/bevy/issues/10436, "world" is now a gltf file instead of a scene
-            scene: models
-                .get(game_assets.world.id())
-                .expect("main level should have been loaded")
-                .scenes[0]
-                .clone(),
+            scene: scene.clone(),
             ..default()
         },
         bevy::prelude::Name::from("world"),
         GameWorldTag,
-        InAppRunning,
+        // the level goes away with AppState::AppRunning
+        StateScoped(AppState::AppRunning),
     ));
 
     next_game_state.set(GameState::InGame)
@@ -46,11 +64,13 @@ pub fn spawn_test(
     keycode: Res<Input<KeyCode>>,
     mut commands: Commands,
 
-    mut game_world: Query<(Entity, &Children), With<GameWorldTag>>,
+    game_world: Query<&Children, With<GameWorldTag>>,
 ) {
     if keycode.just_pressed(KeyCode::T) {
-        let world = game_world.single_mut();
-        let world = world.1[0];
+        let Some(world) = level_root(&game_world) else {
+            warn!("no level to spawn the test pickup in");
+            return;
+        };
 
         let mut rng = rand::thread_rng();
         let range = 5.5;
@@ -68,7 +88,7 @@ pub fn spawn_test(
         let new_entity = commands
             .spawn((
                 BluePrintBundle {
-                    blueprint: BlueprintName("Health_Pickup".to_string()),
+                    blueprint: BlueprintName(HEALTH_PICKUP_BLUEPRINT.to_string()),
                     transform: TransformBundle::from_transform(Transform::from_xyz(x, 2.0, y)),
                     ..Default::default()
                 },
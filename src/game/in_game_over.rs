@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+
+use crate::state::{AppState, GameState, StateScoped};
+
+use super::{menu_root, menu_text, menu_title, RestartLevel, Wallet, WaveSpawner};
+
+pub fn setup_game_over(mut commands: Commands, spawner: Res<WaveSpawner>, wallet: Res<Wallet>) {
+    commands
+        .spawn((
+            NodeBundle {
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+                ..menu_root()
+            },
+            StateScoped(GameState::InGameOver),
+        ))
+        .with_children(|parent| {
+            parent.spawn(menu_title("GAME OVER"));
+            parent.spawn(menu_text(
+                format!("waves survived: {}", spawner.current),
+                18.0,
+            ));
+            parent.spawn(menu_text(format!("gold: {}", wallet.gold), 18.0));
+            parent.spawn(menu_text(
+                "press R to restart, M to return to the main menu",
+                18.0,
+            ));
+        });
+}
+
+pub fn game_over(
+    keycode: Res<Input<KeyCode>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    if keycode.just_pressed(KeyCode::R) {
+        commands.add(RestartLevel);
+    } else if keycode.just_pressed(KeyCode::M) {
+        next_game_state.set(GameState::None);
+        next_app_state.set(AppState::MenuRunning);
+    }
+}
@@ -1,119 +1,318 @@
+use bevy::app::AppExit;
 use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
 
-use crate::state::{AppState, GameState, InMainMenu};
+use crate::{
+    assets::CoreAssets,
+    state::{AppState, StateScoped},
+};
 
-pub fn setup_main_menu(
-    mut commands: Commands,
-    mut next_app_state: ResMut<NextState<AppState>>) {
-    commands.spawn((Camera2dBundle::default(), InMainMenu));
+use super::{
+    fullscreen_label, level_index, list_saves, menu_root, menu_title, read_save, spawn_menu_button,
+    toggle_fullscreen, CurrentLevel, LevelCatalog, MenuBack, MenuButtonPressed, MenuFocus,
+    PendingLoad,
+};
 
-    // Skip the menu go straight to the game state
-    next_app_state.set(AppState::AppLoading);
+#[derive(Resource, Debug, Default)]
+pub struct MainMenuSettings {
+    /// go straight to the game on startup instead of showing the menu, handy during development
+    pub skip_to_game: bool,
+}
 
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MainMenuPage {
+    #[default]
+    Main,
+    LevelSelect,
+    LoadGame,
+    Settings,
+}
 
-    commands.spawn((
-        TextBundle::from_section(
-            "SOME GAME TITLE !!",
-            TextStyle {
-                //font: asset_server.load("fonts/FiraMono-Medium.ttf"),
-                font_size: 18.0,
-                color: Color::WHITE,
-                ..Default::default()
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(100.0),
-            left: Val::Px(200.0),
-            ..default()
-        }),
-        InMainMenu,
-    ));
+#[derive(Component, Debug, Clone)]
+pub enum MainMenuAction {
+    NewGame,
+    /// starts the level with this index in the level catalog
+    Play(usize),
+    /// loads the most recent save
+    Continue,
+    LoadGame,
+    Load(String),
+    Settings,
+    ToggleFullscreen,
+    Back,
+    Exit,
+}
 
-    commands.spawn((
-        TextBundle::from_section(
-            "New Game (press Enter to start, press T once the game is started for demo spawning)",
-            TextStyle {
-                //font: asset_server.load("fonts/FiraMono-Medium.ttf"),
-                font_size: 18.0,
-                color: Color::WHITE,
-                ..Default::default()
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(200.0),
-            left: Val::Px(200.0),
-            ..default()
-        }),
-        InMainMenu,
-    ));
+#[derive(Component)]
+pub struct MainMenuRoot;
 
-    /*
-    commands.spawn((
-        TextBundle::from_section(
-            "Load Game",
-            TextStyle {
-                //font: asset_server.load("fonts/FiraMono-Medium.ttf"),
-                font_size: 18.0,
-                color: Color::WHITE,
-                ..Default::default()
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(250.0),
-            left: Val::Px(200.0),
-            ..default()
-        }),
-        InMainMenu
-    ));
+pub fn setup_main_menu(
+    mut commands: Commands,
+    mut settings: ResMut<MainMenuSettings>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if settings.skip_to_game {
+        // only once, coming back to the menu later on should show it
+        settings.skip_to_game = false;
+        next_app_state.set(AppState::AppLoading);
+        return;
+    }
 
     commands.spawn((
-        TextBundle::from_section(
-            "Exit Game",
-            TextStyle {
-                //font: asset_server.load("fonts/FiraMono-Medium.ttf"),
-                font_size: 18.0,
-                color: Color::WHITE,
-                ..Default::default()
-            },
-        )
-        .with_style(Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(300.0),
-            left: Val::Px(200.0),
-            ..default()
-        }),
-        InMainMenu
-    ));*/
+        Camera2dBundle::default(),
+        StateScoped(AppState::MenuRunning),
+    ));
+    commands.insert_resource(MainMenuPage::Main);
 }
 
-pub fn teardown_main_menu(bla: Query<Entity, With<InMainMenu>>, mut commands: Commands) {
-    for bli in bla.iter() {
-        commands.entity(bli).despawn_recursive();
+// respawns the menu entries whenever the page changes
+pub fn build_main_menu_page(
+    page: Res<MainMenuPage>,
+    roots: Query<Entity, With<MainMenuRoot>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    core_assets: Res<CoreAssets>,
+    level_catalogs: Res<Assets<LevelCatalog>>,
+    mut focus: ResMut<MenuFocus>,
+    mut commands: Commands,
+) {
+    if !page.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
     }
+    focus.0 = 0;
+
+    let saves = list_saves();
+    commands
+        .spawn((
+            menu_root(),
+            MainMenuRoot,
+            StateScoped(AppState::MenuRunning),
+        ))
+        .with_children(|parent| match *page {
+            MainMenuPage::Main => {
+                parent.spawn(menu_title("SOME GAME TITLE !!"));
+                let mut entries = vec![("New Game", MainMenuAction::NewGame)];
+                if !saves.is_empty() {
+                    entries.push(("Continue", MainMenuAction::Continue));
+                }
+                entries.extend([
+                    ("Load Game", MainMenuAction::LoadGame),
+                    ("Settings", MainMenuAction::Settings),
+                    ("Exit", MainMenuAction::Exit),
+                ]);
+                for (index, (label, action)) in entries.into_iter().enumerate() {
+                    spawn_menu_button(parent, index, label, action);
+                }
+            }
+            MainMenuPage::LevelSelect => {
+                parent.spawn(menu_title("Select Level"));
+                let levels = level_catalogs
+                    .get(&core_assets.levels)
+                    .map_or(&[][..], |catalog| &catalog.levels[..]);
+                for (index, level) in levels.iter().enumerate() {
+                    let label = if level.description.is_empty() {
+                        level.name.clone()
+                    } else {
+                        format!("{} - {}", level.name, level.description)
+                    };
+                    spawn_menu_button(parent, index, label, MainMenuAction::Play(index));
+                }
+                spawn_menu_button(parent, levels.len(), "Back", MainMenuAction::Back);
+            }
+            MainMenuPage::LoadGame => {
+                parent.spawn(menu_title("Load Game"));
+                if saves.is_empty() {
+                    parent.spawn(TextBundle::from_section(
+                        "no saves yet",
+                        TextStyle {
+                            font_size: 18.0,
+                            color: Color::GRAY,
+                            ..Default::default()
+                        },
+                    ));
+                }
+                for (index, save) in saves.iter().enumerate() {
+                    spawn_menu_button(parent, index, save, MainMenuAction::Load(save.clone()));
+                }
+                spawn_menu_button(parent, saves.len(), "Back", MainMenuAction::Back);
+            }
+            MainMenuPage::Settings => {
+                parent.spawn(menu_title("Settings"));
+                spawn_menu_button(
+                    parent,
+                    0,
+                    fullscreen_label(windows.get_single().ok()),
+                    MainMenuAction::ToggleFullscreen,
+                );
+                spawn_menu_button(parent, 1, "Back", MainMenuAction::Back);
+            }
+        });
 }
 
 pub fn main_menu(
-    keycode: Res<Input<KeyCode>>,
-
+    mut pressed_events: EventReader<MenuButtonPressed>,
+    mut back_events: EventReader<MenuBack>,
+    actions: Query<&MainMenuAction>,
+    mut page: ResMut<MainMenuPage>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    core_assets: Res<CoreAssets>,
+    level_catalogs: Res<Assets<LevelCatalog>>,
+    mut current_level: ResMut<CurrentLevel>,
     mut next_app_state: ResMut<NextState<AppState>>,
-    // mut next_game_state: ResMut<NextState<GameState>>,
-    // mut save_requested_events: EventWriter<SaveRequest>,
-    // mut load_requested_events: EventWriter<LoadRequest>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut commands: Commands,
 ) {
-    if keycode.just_pressed(KeyCode::Return) {
-        next_app_state.set(AppState::AppLoading);
-        // next_game_state.set(GameState::None);
+    let catalog = level_catalogs.get(&core_assets.levels);
+    // the save gets loaded once its level is ready
+    let mut load = |save: &str| {
+        if let Ok(save_file) = read_save(save) {
+            current_level.0 = level_index(catalog, &save_file.level);
+        }
+        commands.insert_resource(PendingLoad(save.to_string()));
+    };
+
+    if back_events.read().count() > 0 && *page != MainMenuPage::Main {
+        *page = MainMenuPage::Main;
     }
 
-    if keycode.just_pressed(KeyCode::L) {
-        next_app_state.set(AppState::AppLoading);
-        // load_requested_events.send(LoadRequest { path: "toto".into() })
+    for MenuButtonPressed { button } in pressed_events.read() {
+        let Ok(action) = actions.get(*button) else {
+            continue;
+        };
+        match action {
+            MainMenuAction::NewGame => {
+                // no need to choose when there is a single level
+                if catalog.map_or(0, |catalog| catalog.levels.len()) > 1 {
+                    *page = MainMenuPage::LevelSelect;
+                } else {
+                    current_level.0 = 0;
+                    next_app_state.set(AppState::AppLoading);
+                }
+            }
+            MainMenuAction::Play(level) => {
+                current_level.0 = *level;
+                next_app_state.set(AppState::AppLoading);
+            }
+            MainMenuAction::Continue => {
+                if let Some(save) = list_saves().first() {
+                    load(save);
+                    next_app_state.set(AppState::AppLoading);
+                }
+            }
+            MainMenuAction::Load(save) => {
+                load(save);
+                next_app_state.set(AppState::AppLoading);
+            }
+            MainMenuAction::LoadGame => *page = MainMenuPage::LoadGame,
+            MainMenuAction::Settings => *page = MainMenuPage::Settings,
+            MainMenuAction::ToggleFullscreen => {
+                if let Ok(mut window) = windows.get_single_mut() {
+                    toggle_fullscreen(&mut window);
+                }
+                // refresh the label
+                page.set_changed();
+            }
+            MainMenuAction::Back => *page = MainMenuPage::Main,
+            MainMenuAction::Exit => app_exit_events.send(AppExit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{menu_navigation, MenuPlugin};
+    use crate::state::StatePlugin;
+
+    fn menu_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            StatePlugin,
+            MenuPlugin,
+        ))
+        .init_asset::<LevelCatalog>()
+        .init_resource::<Input<KeyCode>>()
+        .init_resource::<Gamepads>()
+        .init_resource::<Input<GamepadButton>>()
+        .init_resource::<CurrentLevel>()
+        .init_resource::<MainMenuSettings>()
+        .init_resource::<MainMenuPage>()
+        .add_systems(OnEnter(AppState::MenuRunning), setup_main_menu)
+        .add_systems(
+            Update,
+            (build_main_menu_page, main_menu)
+                .chain()
+                .after(menu_navigation)
+                .run_if(in_state(AppState::MenuRunning)),
+        );
+        let levels = app.world.resource_mut::<Assets<LevelCatalog>>().add(
+            ron::from_str::<LevelCatalog>(
+                r#"(levels: [
+                    (name: "Level 1", world: "models/World.glb", waves: "levels/level1.waves.ron"),
+                    (name: "Level 2", world: "models/World.glb", waves: "levels/level1.waves.ron"),
+                ])"#,
+            )
+            .unwrap(),
+        );
+        app.insert_resource(CoreAssets { levels });
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::MenuRunning);
+        // the menu gets set up, then its entries spawned
+        app.update();
+        app.update();
+        app
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.update();
+        let mut keycode = app.world.resource_mut::<Input<KeyCode>>();
+        keycode.release(key);
+        keycode.clear();
+    }
+
+    #[test]
+    fn new_game_goes_through_the_level_select() {
+        let mut app = menu_app();
+        assert_eq!(*app.world.resource::<MainMenuPage>(), MainMenuPage::Main);
+
+        // New Game is the first entry, there is more than one level to choose from
+        press(&mut app, KeyCode::Return);
+        assert_eq!(
+            *app.world.resource::<MainMenuPage>(),
+            MainMenuPage::LevelSelect
+        );
+        app.update();
+
+        // & back again
+        press(&mut app, KeyCode::Escape);
+        assert_eq!(*app.world.resource::<MainMenuPage>(), MainMenuPage::Main);
+        app.update();
+        press(&mut app, KeyCode::Return);
+        app.update();
+
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.world.resource::<MenuFocus>().0, 1);
+        press(&mut app, KeyCode::Return);
+        assert_eq!(app.world.resource::<CurrentLevel>().0, 1);
+        app.update();
+        assert_eq!(
+            *app.world.resource::<State<AppState>>().get(),
+            AppState::AppLoading
+        );
     }
 
-    if keycode.just_pressed(KeyCode::S) {
-        // save_requested_events.send(SaveRequest { path: "toto".into() })
+    #[test]
+    fn going_up_from_the_first_entry_wraps_around_to_exit() {
+        let mut app = menu_app();
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Space);
+        let exits = app.world.resource::<Events<AppExit>>();
+        assert_eq!(exits.get_reader().read(exits).count(), 1);
     }
 }
@@ -0,0 +1,274 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::state::{AppState, GameState, StateScoped};
+
+use super::{
+    fullscreen_label, gamepad_just_pressed, menu_root, menu_title, spawn_menu_button,
+    toggle_fullscreen, MenuBack, MenuButtonPressed, MenuFocus, RestartLevel, SaveRequest,
+};
+
+#[derive(Resource, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMenuPage {
+    #[default]
+    Main,
+    Settings,
+}
+
+#[derive(Component, Debug, Clone)]
+pub enum PauseMenuAction {
+    Resume,
+    Restart,
+    Settings,
+    Save,
+    Quit,
+    ToggleFullscreen,
+    Back,
+}
+
+#[derive(Component)]
+pub struct PauseMenuRoot;
+
+#[derive(Component)]
+/// Marks the animation players stopped by the pause, so only those get resumed
+pub struct PausedAnimation;
+
+pub fn pause_input(
+    keycode: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keycode.just_pressed(KeyCode::Escape)
+        || gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::Start)
+    {
+        next_game_state.set(GameState::Paused);
+    }
+}
+
+// stopping the virtual clock freezes everything driven by time: wave timers, tower cooldowns, the character controller...
+pub fn pause_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer)>,
+    mut commands: Commands,
+) {
+    info!("pausing game");
+    time.pause();
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        if !animation_player.is_paused() {
+            animation_player.pause();
+            commands.entity(entity).insert(PausedAnimation);
+        }
+    }
+    commands.insert_resource(PauseMenuPage::Main);
+}
+
+pub fn resume_game(
+    mut time: ResMut<Time<Virtual>>,
+    mut animation_players: Query<(Entity, &mut AnimationPlayer), With<PausedAnimation>>,
+    mut commands: Commands,
+) {
+    info!("resuming game");
+    time.unpause();
+    for (entity, mut animation_player) in animation_players.iter_mut() {
+        animation_player.resume();
+        commands.entity(entity).remove::<PausedAnimation>();
+    }
+}
+
+pub fn build_pause_menu_page(
+    page: Res<PauseMenuPage>,
+    roots: Query<Entity, With<PauseMenuRoot>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut focus: ResMut<MenuFocus>,
+    mut commands: Commands,
+) {
+    if !page.is_changed() {
+        return;
+    }
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+    focus.0 = 0;
+
+    let mut root = menu_root();
+    root.background_color = Color::rgba(0.0, 0.0, 0.0, 0.7).into();
+    commands
+        .spawn((root, PauseMenuRoot, StateScoped(GameState::Paused)))
+        .with_children(|parent| match *page {
+            PauseMenuPage::Main => {
+                parent.spawn(menu_title("Paused"));
+                for (index, (label, action)) in [
+                    ("Resume", PauseMenuAction::Resume),
+                    ("Restart level", PauseMenuAction::Restart),
+                    ("Settings", PauseMenuAction::Settings),
+                    ("Save", PauseMenuAction::Save),
+                    ("Quit to main menu", PauseMenuAction::Quit),
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    spawn_menu_button(parent, index, label, action);
+                }
+            }
+            PauseMenuPage::Settings => {
+                parent.spawn(menu_title("Settings"));
+                spawn_menu_button(
+                    parent,
+                    0,
+                    fullscreen_label(windows.get_single().ok()),
+                    PauseMenuAction::ToggleFullscreen,
+                );
+                spawn_menu_button(parent, 1, "Back", PauseMenuAction::Back);
+            }
+        });
+}
+
+pub fn pause_menu(
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut pressed_events: EventReader<MenuButtonPressed>,
+    mut back_events: EventReader<MenuBack>,
+    actions: Query<&PauseMenuAction>,
+    mut page: ResMut<PauseMenuPage>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut save_requests: EventWriter<SaveRequest>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    // Escape goes back from the settings, & resumes from the main page
+    if back_events.read().count() > 0 {
+        match *page {
+            PauseMenuPage::Settings => *page = PauseMenuPage::Main,
+            PauseMenuPage::Main => next_game_state.set(GameState::InGame),
+        }
+    } else if gamepad_just_pressed(&gamepads, &gamepad_buttons, GamepadButtonType::Start) {
+        next_game_state.set(GameState::InGame);
+    }
+
+    for MenuButtonPressed { button } in pressed_events.read() {
+        let Ok(action) = actions.get(*button) else {
+            continue;
+        };
+        match action {
+            PauseMenuAction::Resume => next_game_state.set(GameState::InGame),
+            PauseMenuAction::Restart => commands.add(RestartLevel),
+            PauseMenuAction::Settings => *page = PauseMenuPage::Settings,
+            PauseMenuAction::Save => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                save_requests.send(SaveRequest {
+                    path: format!("save_{}", timestamp),
+                });
+            }
+            PauseMenuAction::Quit => {
+                next_game_state.set(GameState::None);
+                next_app_state.set(AppState::MenuRunning);
+            }
+            PauseMenuAction::ToggleFullscreen => {
+                if let Ok(mut window) = windows.get_single_mut() {
+                    toggle_fullscreen(&mut window);
+                }
+                // refresh the label
+                page.set_changed();
+            }
+            PauseMenuAction::Back => *page = PauseMenuPage::Main,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::assets::GameAssets;
+    use crate::game::{
+        Tower, TowerCooldown, TowersPlugin, WaveDefinition, WaveSpawner, WavesDefinition,
+        WavesPlugin,
+    };
+    use crate::state::StatePlugin;
+
+    fn set_game_state(app: &mut App, state: GameState) {
+        app.world.resource_mut::<NextState<GameState>>().set(state);
+        app.update();
+    }
+
+    #[test]
+    fn pausing_freezes_wave_timers_and_tower_cooldowns() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            StatePlugin,
+            TowersPlugin,
+            WavesPlugin,
+        ))
+        .init_asset::<WavesDefinition>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            0.1,
+        )))
+        .add_systems(OnEnter(GameState::Paused), pause_game)
+        .add_systems(OnExit(GameState::Paused), resume_game);
+
+        let wave: WaveDefinition = ron::from_str(
+            r#"(enemy: "Fox", count: 1, interval: 1.0, spawn_point: "EnemySpawn",
+                path: "EnemyPath", delay: 60.0, speed: 1.0, health: 25.0)"#,
+        )
+        .unwrap();
+        let waves = app
+            .world
+            .resource_mut::<Assets<WavesDefinition>>()
+            .add(WavesDefinition {
+                waves: vec![wave.clone()],
+            });
+        app.insert_resource(GameAssets {
+            world: Handle::default(),
+            models: default(),
+            waves,
+            towers: Handle::default(),
+            materials: Handle::default(),
+            sounds: Handle::default(),
+        })
+        .insert_resource(WaveSpawner::starting_at(0, Some(&wave)));
+        let tower = app
+            .world
+            .spawn((Tower::default(), TransformBundle::default()))
+            .id();
+        set_game_state(&mut app, GameState::InGame);
+        // as if the tower had just fired
+        app.world
+            .entity_mut(tower)
+            .insert(TowerCooldown(Timer::from_seconds(30.0, TimerMode::Once)));
+
+        let snapshot = |app: &App| {
+            (
+                app.world.resource::<WaveSpawner>().progress(),
+                app.world.get::<TowerCooldown>(tower).unwrap().elapsed(),
+            )
+        };
+        app.update();
+        let before_pause = snapshot(&app);
+        assert!(before_pause.0.elapsed > 0.0);
+
+        set_game_state(&mut app, GameState::Paused);
+        let paused = snapshot(&app);
+        for _ in 0..5 {
+            app.update();
+        }
+        assert!(app.world.resource::<Time<Virtual>>().is_paused());
+        assert_eq!(snapshot(&app), paused);
+
+        set_game_state(&mut app, GameState::InGame);
+        app.update();
+        let resumed = snapshot(&app);
+        assert!(resumed.0.elapsed > paused.0.elapsed);
+        assert!(resumed.1 > paused.1);
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::ecs::system::Command;
+use bevy::prelude::*;
+use bevy_asset_loader::prelude::*;
+use bevy_gltf_blueprints::GameWorldTag;
+use serde::Deserialize;
+
+use crate::{
+    assets::{CoreAssets, GameAssets},
+    state::{AppState, GameState},
+};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelDefinition {
+    /// shown in the level select, also used to find the level of a save
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// path of the gltf file containing the level, relative to the assets folder
+    pub world: String,
+    /// index of the scene to spawn in the gltf file
+    #[serde(default)]
+    pub scene: usize,
+    /// path of the wave definitions of the level, relative to the assets folder
+    pub waves: String,
+    /// overrides the default starting gold for this level
+    #[serde(default)]
+    pub starting_gold: Option<u32>,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct LevelCatalog {
+    pub levels: Vec<LevelDefinition>,
+}
+
+/// Index (in the level catalog) of the level being played, or about to be
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct CurrentLevel(pub usize);
+
+pub fn current_level_definition<'a>(
+    core_assets: &CoreAssets,
+    catalogs: &'a Assets<LevelCatalog>,
+    current_level: &CurrentLevel,
+) -> Option<&'a LevelDefinition> {
+    catalogs
+        .get(&core_assets.levels)
+        .and_then(|catalog| catalog.levels.get(current_level.0))
+}
+
+/// What gets spawned into the level goes under the root of its scene, to be unloaded along with it.
+/// There is none while the level is being unloaded or restarted
+pub fn level_root(game_worlds: &Query<&Children, With<GameWorldTag>>) -> Option<Entity> {
+    game_worlds.get_single().ok()?.first().copied()
+}
+
+/// Index of the level called `name`, saves made before there were several levels belong to the first one
+pub fn level_index(catalog: Option<&LevelCatalog>, name: &str) -> usize {
+    catalog
+        .and_then(|catalog| catalog.levels.iter().position(|level| level.name == name))
+        .unwrap_or(0)
+}
+
+// the "world" & "waves" keys of the game assets depend on the level, so they are registered just before loading them
+pub fn register_level_assets(
+    core_assets: Res<CoreAssets>,
+    catalogs: Res<Assets<LevelCatalog>>,
+    current_level: Res<CurrentLevel>,
+    mut dynamic_assets: ResMut<DynamicAssets>,
+) {
+    let Some(level) = current_level_definition(&core_assets, &catalogs, &current_level) else {
+        error!("no level {} in the level catalog", current_level.0);
+        return;
+    };
+    info!("loading level {} from {}", level.name, level.world);
+    dynamic_assets.register_asset(
+        "world",
+        Box::new(StandardDynamicAsset::File {
+            path: level.world.clone(),
+        }),
+    );
+    dynamic_assets.register_asset(
+        "waves",
+        Box::new(StandardDynamicAsset::File {
+            path: level.waves.clone(),
+        }),
+    );
+}
+
+/// Starts the current level over by passing through AppState::AppRestarting, so nothing gets loaded again:
+/// the level's entities go away when exiting AppState::AppRunning, then get set up anew when entering it back
+pub struct RestartLevel;
+
+impl Command for RestartLevel {
+    fn apply(self, world: &mut World) {
+        info!("restarting level {}", world.resource::<CurrentLevel>().0);
+        world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::None);
+        world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::AppRestarting);
+    }
+}
+
+pub fn restart_level(mut next_app_state: ResMut<NextState<AppState>>) {
+    next_app_state.set(AppState::AppRunning);
+}
+
+// dropping the handles lets the previous level's world, blueprints & waves get unloaded before the next one loads,
+// unless the same level is about to be restarted (the state is already the next one when exiting)
+pub fn unload_level(mut commands: Commands) {
+    commands.remove_resource::<GameAssets>();
+}
+
+pub struct LevelsPlugin;
+impl Plugin for LevelsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentLevel>()
+            .add_systems(OnEnter(AppState::AppLoading), register_level_assets)
+            .add_systems(OnEnter(AppState::AppRestarting), restart_level)
+            .add_systems(
+                OnExit(AppState::AppRunning),
+                unload_level.run_if(not(in_state(AppState::AppRestarting))),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{StatePlugin, StateScoped};
+    use bevy_gltf_blueprints::BlueprintName;
+
+    // stands in for setup_game, which needs the actual gltf of the level
+    fn spawn_level(mut commands: Commands) {
+        commands
+            .spawn((GameWorldTag, StateScoped(AppState::AppRunning)))
+            .with_children(|world| {
+                world.spawn(BlueprintName("Tower".into()));
+            });
+    }
+
+    fn count<F: bevy::ecs::query::ReadOnlyWorldQuery>(app: &mut App) -> usize {
+        app.world.query_filtered::<(), F>().iter(&app.world).count()
+    }
+
+    #[test]
+    fn restarting_sets_the_level_up_once_without_unloading_it() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin, LevelsPlugin))
+            .insert_resource(GameAssets {
+                world: Handle::default(),
+                models: default(),
+                waves: Handle::default(),
+                towers: Handle::default(),
+                materials: Handle::default(),
+                sounds: Handle::default(),
+            })
+            .add_systems(OnEnter(AppState::AppRunning), spawn_level);
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::AppRunning);
+        app.update();
+        assert_eq!(count::<With<GameWorldTag>>(&mut app), 1);
+
+        for _ in 0..2 {
+            RestartLevel.apply(&mut app.world);
+            app.update();
+            assert_eq!(
+                *app.world.resource::<State<AppState>>().get(),
+                AppState::AppRestarting
+            );
+            app.update();
+            assert_eq!(
+                *app.world.resource::<State<AppState>>().get(),
+                AppState::AppRunning
+            );
+            assert_eq!(count::<With<GameWorldTag>>(&mut app), 1);
+            assert_eq!(count::<With<BlueprintName>>(&mut app), 1);
+        }
+        assert!(app.world.contains_resource::<GameAssets>());
+    }
+}
@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
+const FOCUSED_BUTTON_COLOR: Color = Color::rgb(0.35, 0.35, 0.55);
+const TEXT_COLOR: Color = Color::WHITE;
+
+#[derive(Component, Debug)]
+/// A menu entry, navigable with the keyboard, the mouse or a gamepad: `index` gives the navigation order
+pub struct MenuButton {
+    pub index: usize,
+}
+
+/// Index of the menu button with the focus
+#[derive(Resource, Debug, Default, Deref, DerefMut)]
+pub struct MenuFocus(pub usize);
+
+#[derive(Event, Debug)]
+pub struct MenuButtonPressed {
+    pub button: Entity,
+}
+
+/// Sent when going back (Escape / gamepad East) in a menu
+#[derive(Event, Debug)]
+pub struct MenuBack;
+
+/// Full screen column layout to put menu entries in
+pub fn menu_root() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(10.0),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+pub fn menu_text(text: impl Into<String>, font_size: f32) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            //font: asset_server.load("fonts/FiraMono-Medium.ttf"),
+            font_size,
+            color: TEXT_COLOR,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn menu_title(text: impl Into<String>) -> TextBundle {
+    menu_text(text, 36.0).with_style(Style {
+        margin: UiRect::bottom(Val::Px(30.0)),
+        ..default()
+    })
+}
+
+/// Spawns a button with a label, `action` being whatever the menu needs to know what to do when it gets pressed
+pub fn spawn_menu_button(
+    parent: &mut ChildBuilder,
+    index: usize,
+    label: impl Into<String>,
+    action: impl Component,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(260.0),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            MenuButton { index },
+            action,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font_size: 18.0,
+                    color: TEXT_COLOR,
+                    ..Default::default()
+                },
+            ));
+        });
+}
+
+pub fn fullscreen_label(window: Option<&Window>) -> String {
+    let fullscreen = window.map_or(false, |window| window.mode != WindowMode::Windowed);
+    format!("Fullscreen: {}", if fullscreen { "on" } else { "off" })
+}
+
+pub fn toggle_fullscreen(window: &mut Window) {
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
+
+pub fn gamepad_just_pressed(
+    gamepads: &Gamepads,
+    gamepad_buttons: &Input<GamepadButton>,
+    button_type: GamepadButtonType,
+) -> bool {
+    gamepads
+        .iter()
+        .any(|gamepad| gamepad_buttons.just_pressed(GamepadButton::new(gamepad, button_type)))
+}
+
+pub fn menu_navigation(
+    keycode: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    buttons: Query<(Entity, &MenuButton, &Interaction)>,
+    changed_interactions: Query<(Entity, &MenuButton, &Interaction), Changed<Interaction>>,
+    mut focus: ResMut<MenuFocus>,
+    mut pressed_events: EventWriter<MenuButtonPressed>,
+    mut back_events: EventWriter<MenuBack>,
+) {
+    let count = buttons.iter().count();
+    if count == 0 {
+        return;
+    }
+
+    // mouse
+    for (button, menu_button, interaction) in changed_interactions.iter() {
+        match interaction {
+            Interaction::Hovered => focus.0 = menu_button.index,
+            Interaction::Pressed => {
+                focus.0 = menu_button.index;
+                pressed_events.send(MenuButtonPressed { button });
+            }
+            Interaction::None => {}
+        }
+    }
+
+    // keyboard & gamepad
+    let pressed = |keys: &[KeyCode], button_type: GamepadButtonType| {
+        keycode.any_just_pressed(keys.iter().copied())
+            || gamepad_just_pressed(&gamepads, &gamepad_buttons, button_type)
+    };
+    if pressed(&[KeyCode::Up, KeyCode::W], GamepadButtonType::DPadUp) {
+        focus.0 = (focus.0 + count - 1) % count;
+    }
+    if pressed(&[KeyCode::Down, KeyCode::S], GamepadButtonType::DPadDown) {
+        focus.0 = (focus.0 + 1) % count;
+    }
+    // the page might have changed under us
+    focus.0 = focus.0.min(count - 1);
+
+    if pressed(&[KeyCode::Return, KeyCode::Space], GamepadButtonType::South) {
+        if let Some((button, _, _)) = buttons
+            .iter()
+            .find(|(_, menu_button, _)| menu_button.index == focus.0)
+        {
+            pressed_events.send(MenuButtonPressed { button });
+        }
+    }
+    if pressed(&[KeyCode::Escape], GamepadButtonType::East) {
+        back_events.send(MenuBack);
+    }
+}
+
+pub fn highlight_focused_button(
+    focus: Res<MenuFocus>,
+    mut buttons: Query<(&MenuButton, &mut BackgroundColor)>,
+) {
+    for (menu_button, mut color) in buttons.iter_mut() {
+        let wanted = if menu_button.index == focus.0 {
+            FOCUSED_BUTTON_COLOR
+        } else {
+            BUTTON_COLOR
+        };
+        if color.0 != wanted {
+            color.0 = wanted;
+        }
+    }
+}
+
+pub struct MenuPlugin;
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .add_event::<MenuButtonPressed>()
+            .add_event::<MenuBack>()
+            .add_systems(Update, (menu_navigation, highlight_focused_button).chain());
+    }
+}
@@ -1,27 +1,68 @@
 pub mod in_game;
 use std::time::Duration;
 
-use bevy::core_pipeline::bloom::BloomSettings;
-use bevy::core_pipeline::experimental::taa::TemporalAntiAliasBundle;
 use bevy_gltf_blueprints::{AnimationPlayerLink, Animations};
-use bevy_xpbd_3d::prelude::{
-    Collision, CollisionEnded, CollisionStarted};
 
 use bevy_xpbd_3d::{
     math::*, prelude::*, PhysicsSchedule, PhysicsStepSet, SubstepSchedule, SubstepSet,
 };
 
 use seldom_state::prelude::StateMachine;
-use smooth_bevy_cameras::LookTransformPlugin;
+use serde::Deserialize;
 
 pub use in_game::*;
 
+pub mod menu;
+pub use menu::*;
+
 pub mod in_main_menu;
 pub use in_main_menu::*;
 
+pub mod in_game_over;
+pub use in_game_over::*;
+
+pub mod in_pause_menu;
+pub use in_pause_menu::*;
+
 pub mod picking;
 pub use picking::*;
 
+pub mod waves;
+pub use waves::*;
+
+pub mod paths;
+pub use paths::*;
+
+pub mod towers;
+pub use towers::*;
+
+pub mod health;
+pub use health::*;
+
+pub mod projectiles;
+pub use projectiles::*;
+
+pub mod build;
+pub use build::*;
+
+pub mod economy;
+pub use economy::*;
+
+pub mod base;
+pub use base::*;
+
+pub mod save_load;
+pub use save_load::*;
+
+pub mod levels;
+pub use levels::*;
+
+pub mod physics_materials;
+pub use physics_materials::*;
+
+pub mod sound_cues;
+pub use sound_cues::*;
+
 use crate::{
     insert_dependant_component,
     state::{AppState, GameState},
@@ -39,13 +80,10 @@ pub use plugin_player::*;
 // pub mod controller_character;
 // pub use controller_character::*;
 
-pub mod controller_camera;
-pub use controller_camera::*;
-
 // this file is just for demo purposes, contains various types of components, systems etc
 
-#[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[derive(Component, Reflect, Default, Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[reflect(Component, Default)]
 pub enum SoundMaterial {
     Metal,
     Wood,
@@ -59,39 +97,24 @@ pub enum SoundMaterial {
 
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 /// Demo component showing auto injection of components
 pub struct ShouldBeWithPlayer;
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 /// Demo marker component
 pub struct Interactible;
 
 
 
-// collision tests/debug
-pub fn test_collision_events(
-    mut collision_started_events: EventReader<CollisionStarted>,
-    mut collision_ended_events: EventReader<CollisionEnded>,
-) {
-    for CollisionStarted(entity1, entity2) in collision_started_events.read() {
-        println!("collision started")
-    }
-
-    for CollisionEnded(entity1, entity2) in collision_ended_events.read() {
-        println!("collision ended")
-    }
-}
-
-
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 /// Demo marker component
 pub struct Fox;
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 /// Demo marker component
 pub struct Robot;
 
@@ -100,10 +123,22 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
+                MenuPlugin,
                 PickingPlugin, 
                 PlayerPlugin,
+                WavesPlugin,
+                PathsPlugin,
+                TowersPlugin,
+                HealthPlugin,
+                ProjectilesPlugin,
+                BuildPlugin,
+                EconomyPlugin,
+                BasePlugin,
+                SaveLoadPlugin,
+                LevelsPlugin,
+                PhysicsMaterialsPlugin,
+                SoundCuesPlugin::default(),
                 //CharacterControllerPlugin, 
-                LookTransformPlugin
             ))
             .register_type::<Interactible>()
             .register_type::<SoundMaterial>()
@@ -121,8 +156,6 @@ impl Plugin for GamePlugin {
             //     SubstepSchedule,
             //     kinematic_collision.in_set(SubstepSet::SolveUserConstraints),
             // )
-            .add_systems(Startup, (camera_setup, ))
-            //.add_systems(Startup, setup)
             .add_systems(
                 Update,
                 (
@@ -132,49 +165,39 @@ impl Plugin for GamePlugin {
                     
                     fox_test,
 
-
-                    move_camera_system,
-                    //camera_setup, 
-                    test_collision_events,
                     spawn_test,
                 )
                     .run_if(in_state(GameState::InGame)), 
             )
             .add_systems(OnEnter(AppState::MenuRunning), setup_main_menu)
-            .add_systems(OnExit(AppState::MenuRunning), teardown_main_menu)
-            .add_systems(Update, (main_menu))
+            .init_resource::<MainMenuSettings>()
+            .init_resource::<MainMenuPage>()
+            .add_systems(
+                Update,
+                (build_main_menu_page, main_menu)
+                    .chain()
+                    .after(menu_navigation)
+                    .run_if(in_state(AppState::MenuRunning)),
+            )
+            .add_systems(OnEnter(GameState::InGameOver), setup_game_over)
+            .add_systems(Update, game_over.run_if(in_state(GameState::InGameOver)))
+            .init_resource::<PauseMenuPage>()
+            .add_systems(OnEnter(GameState::Paused), pause_game)
+            .add_systems(OnExit(GameState::Paused), resume_game)
+            .add_systems(Update, pause_input.run_if(in_state(GameState::InGame)))
+            .add_systems(
+                Update,
+                (build_pause_menu_page, pause_menu)
+                    .chain()
+                    .after(menu_navigation)
+                    .run_if(in_state(GameState::Paused)),
+            )
             .add_systems(OnEnter(AppState::AppRunning), setup_game);
     }
 }
  
 
 
-fn setup(mut commands: Commands) {
-    commands
-        .spawn(Camera3dBundle {
-            transform: Transform::from_translation(Vec3::new(0.0, 4.0, 12.0))
-                .looking_at(Vec3::ZERO, Vec3::Y),
-            camera: Camera {
-                hdr: true,
-                ..default()
-            },
-            ..default()
-        })
-        .insert(BloomSettings {
-            intensity: 0.1,
-            ..default()
-        })
-        .insert(TemporalAntiAliasBundle::default())
-        .insert(Name::new("MainCamera"));
-        //.insert(PlayerFollowingCamera);
-
-    // commands.insert_resource(AmbientLight {
-    //     color: Color::WHITE,
-    //     brightness: 0.2,
-    // });
-}
-
-
 pub fn fox_test(
     animated_foxes: Query<(&AnimationPlayerLink, &Animations), With<Fox>>,
     mut animation_players: Query<&mut AnimationPlayer>,
@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_gltf_blueprints::GltfBlueprintsSet;
+
+use crate::state::GameState;
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Component for level designers: all the waypoints sharing the same `path` form one path, walked in `index` order
+pub struct PathWaypoint {
+    pub path: String,
+    pub index: u32,
+}
+
+#[derive(Component, Reflect, Debug)]
+#[reflect(Component)]
+/// Add this component to an entity to have it walk along one of the level's paths
+pub struct PathFollower {
+    pub path: String,
+    /// in units per second
+    pub speed: f32,
+    /// index of the waypoint we are currently walking to
+    pub next_waypoint: usize,
+    /// how far along the path we are, useful to know who is in the lead
+    pub distance_travelled: f32,
+}
+impl Default for PathFollower {
+    fn default() -> Self {
+        PathFollower {
+            path: String::new(),
+            speed: 2.0,
+            next_waypoint: 0,
+            distance_travelled: 0.0,
+        }
+    }
+}
+
+impl PathFollower {
+    pub fn new(path: impl Into<String>, speed: f32) -> Self {
+        PathFollower {
+            path: path.into(),
+            speed,
+            ..default()
+        }
+    }
+}
+
+/// All the paths of the current level, by name, as ordered lists of world positions
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+pub struct Paths(pub HashMap<String, Vec<Vec3>>);
+
+#[derive(Event, Debug)]
+pub struct PathEndReached {
+    pub entity: Entity,
+    pub path: String,
+}
+
+// rebuilds the paths whenever waypoints are added, moved or removed
+pub fn assemble_paths(
+    mut paths: ResMut<Paths>,
+    waypoints: Query<(&PathWaypoint, &GlobalTransform)>,
+    changed_waypoints: Query<
+        (),
+        (
+            With<PathWaypoint>,
+            Or<(Changed<PathWaypoint>, Changed<GlobalTransform>)>,
+        ),
+    >,
+    mut removed_waypoints: RemovedComponents<PathWaypoint>,
+) {
+    let removed = removed_waypoints.read().count();
+    if changed_waypoints.is_empty() && removed == 0 {
+        return;
+    }
+
+    let mut indexed: HashMap<String, Vec<(u32, Vec3)>> = HashMap::new();
+    for (waypoint, transform) in waypoints.iter() {
+        indexed
+            .entry(waypoint.path.clone())
+            .or_default()
+            .push((waypoint.index, transform.translation()));
+    }
+
+    paths.clear();
+    for (name, mut waypoints) in indexed.into_iter() {
+        waypoints.sort_by_key(|(index, _)| *index);
+        info!("assembled path {} with {} waypoints", name, waypoints.len());
+        paths.insert(
+            name,
+            waypoints
+                .into_iter()
+                .map(|(_, position)| position)
+                .collect(),
+        );
+    }
+}
+
+pub fn follow_paths(
+    time: Res<Time>,
+    paths: Res<Paths>,
+    mut followers: Query<(Entity, &mut PathFollower, &mut Transform, &GlobalTransform)>,
+    mut path_end_events: EventWriter<PathEndReached>,
+) {
+    for (entity, mut follower, mut transform, global_transform) in followers.iter_mut() {
+        let Some(waypoints) = paths.get(&follower.path) else {
+            continue;
+        };
+        if follower.next_waypoint >= waypoints.len() {
+            continue;
+        }
+
+        let start = global_transform.translation();
+        let mut position = start;
+        let mut remaining = follower.speed * time.delta_seconds();
+        while remaining > 0.0 && follower.next_waypoint < waypoints.len() {
+            let to_waypoint = waypoints[follower.next_waypoint] - position;
+            let distance = to_waypoint.length();
+            if distance <= remaining {
+                position = waypoints[follower.next_waypoint];
+                remaining -= distance;
+                follower.next_waypoint += 1;
+            } else {
+                position += to_waypoint / distance * remaining;
+                remaining = 0.0;
+            }
+        }
+
+        // the followers are children of the (untransformed) world, so moving them in world space is fine
+        let step = position - start;
+        transform.translation += step;
+        follower.distance_travelled += step.length();
+
+        let heading = Vec3::new(step.x, 0.0, step.z);
+        if heading.length_squared() > 1e-5 {
+            // same convention as the player: models face +Z
+            transform.look_to(-heading, Vec3::Y);
+        }
+
+        if follower.next_waypoint >= waypoints.len() {
+            path_end_events.send(PathEndReached {
+                entity,
+                path: follower.path.clone(),
+            });
+        }
+    }
+}
+
+pub struct PathsPlugin;
+impl Plugin for PathsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<PathWaypoint>()
+            .register_type::<PathFollower>()
+            .init_resource::<Paths>()
+            .add_event::<PathEndReached>()
+            .add_systems(
+                Update,
+                (
+                    assemble_paths.after(GltfBlueprintsSet::AfterSpawn),
+                    follow_paths.run_if(in_state(GameState::InGame)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+    use bevy::transform::TransformPlugin;
+
+    use super::*;
+    use crate::state::StatePlugin;
+
+    fn spawn_waypoint(app: &mut App, path: &str, index: u32, position: Vec3) {
+        let transform = Transform::from_translation(position);
+        app.world.spawn((
+            PathWaypoint {
+                path: path.into(),
+                index,
+            },
+            transform,
+            GlobalTransform::from(transform),
+        ));
+    }
+
+    #[test]
+    fn followers_walk_the_waypoints_in_index_order() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, StatePlugin, PathsPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                1.0,
+            )));
+        spawn_waypoint(&mut app, "EnemyPath", 2, Vec3::new(3.0, 0.0, 0.0));
+        spawn_waypoint(&mut app, "EnemyPath", 0, Vec3::new(1.0, 0.0, 0.0));
+        spawn_waypoint(&mut app, "OtherPath", 0, Vec3::new(0.0, 0.0, 9.0));
+        spawn_waypoint(&mut app, "EnemyPath", 1, Vec3::new(2.0, 0.0, 0.0));
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        app.update();
+
+        let paths = app.world.resource::<Paths>();
+        assert_eq!(
+            paths["EnemyPath"],
+            vec![
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(3.0, 0.0, 0.0),
+            ]
+        );
+        assert_eq!(paths["OtherPath"].len(), 1);
+
+        let follower = app
+            .world
+            .spawn((
+                PathFollower::new("EnemyPath", 2.0),
+                TransformBundle::default(),
+            ))
+            .id();
+        let mut reader = app.world.resource::<Events<PathEndReached>>().get_reader();
+        let mut path_ends = 0;
+        for _ in 0..4 {
+            app.update();
+            path_ends += reader
+                .read(app.world.resource::<Events<PathEndReached>>())
+                .filter(|path_end| path_end.entity == follower)
+                .count();
+        }
+
+        assert_eq!(path_ends, 1);
+        let follower = app.world.get::<PathFollower>(follower).unwrap();
+        assert_eq!(follower.next_waypoint, 3);
+        assert!((follower.distance_travelled - 3.0).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_gltf_blueprints::GltfBlueprintsSet;
+use serde::Deserialize;
+
+use crate::{
+    assets::GameAssets,
+    core::{apply_physics_materials, PhysicsMaterial},
+};
+
+use super::SoundMaterial;
+
+/// The physics material of each sound material, as described in the `*.materials.ron` files
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct PhysicsMaterialTable {
+    pub materials: HashMap<SoundMaterial, PhysicsMaterial>,
+}
+
+// props authored with a SoundMaterial get the matching physics material, unless they have an explicit one
+pub fn sound_material_physics(
+    game_assets: Res<GameAssets>,
+    tables: Res<Assets<PhysicsMaterialTable>>,
+    props: Query<(Entity, &SoundMaterial), (Added<SoundMaterial>, Without<PhysicsMaterial>)>,
+    mut commands: Commands,
+) {
+    let Some(table) = tables.get(&game_assets.materials) else {
+        return;
+    };
+    for (entity, sound_material) in props.iter() {
+        if let Some(material) = table.materials.get(sound_material) {
+            commands.entity(entity).insert(*material);
+        }
+    }
+}
+
+pub struct PhysicsMaterialsPlugin;
+impl Plugin for PhysicsMaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            sound_material_physics
+                .after(GltfBlueprintsSet::AfterSpawn)
+                .before(apply_physics_materials)
+                .run_if(resource_exists::<GameAssets>()),
+        );
+    }
+}
@@ -1,24 +1,41 @@
-use super::Player;
+use super::{Health, MaxHealth, Player};
 use bevy::prelude::*;
 use bevy_gltf_blueprints::GltfBlueprintsSet;
 
+pub const HEALTH_PICKUP_BLUEPRINT: &str = "Health_Pickup";
+
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub struct Pickable;
 
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component, Default)]
+/// How much health picking this up gives back to the player
+pub struct Healer {
+    pub amount: f32,
+}
+
 // very simple, crude picking (as in picking up objects) implementation
 
 pub fn picking(
-    players: Query<&GlobalTransform, With<Player>>,
-    pickables: Query<(Entity, &GlobalTransform), With<Pickable>>,
+    mut players: Query<(&GlobalTransform, Option<&mut Health>, Option<&MaxHealth>), With<Player>>,
+    pickables: Query<(Entity, &GlobalTransform, Option<&Healer>), With<Pickable>>,
     mut commands: Commands,
 ) {
-    for player_transforms in players.iter() {
-        for (pickable, pickable_transforms) in pickables.iter() {
+    for (player_transforms, mut health, max_health) in players.iter_mut() {
+        for (pickable, pickable_transforms, healer) in pickables.iter() {
             let distance = player_transforms
                 .translation()
                 .distance(pickable_transforms.translation());
             if distance < 2.5 {
+                let heal = healer.map(|healer| healer.amount);
+                if let (Some(heal), Some(health)) = (heal, health.as_mut()) {
+                    health.0 += heal;
+                    if let Some(max_health) = max_health {
+                        health.0 = health.0.min(max_health.0);
+                    }
+                    info!("picked up {} health, now at {}", heal, health.0);
+                }
                 commands.entity(pickable).despawn_recursive();
             }
         }
@@ -29,6 +46,7 @@ pub struct PickingPlugin;
 impl Plugin for PickingPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Pickable>()
+            .register_type::<Healer>()
             .add_systems(Update, (picking.after(GltfBlueprintsSet::AfterSpawn),));
     }
 }
@@ -10,7 +10,13 @@ use bevy_xpbd_3d::prelude::*;
 use leafwing_input_manager::prelude::*;
 use seldom_state::prelude::*;
 use seldom_state::trigger::{AndTrigger, OrTrigger};
-use smooth_bevy_cameras::{LookTransform, LookTransformBundle, LookTransformPlugin, Smoother};
+
+use crate::core::{
+    player_controls_enabled, CameraTrackable, CollisionLayerMatrix, CollisionLayersProxy, GameLayer,
+};
+use crate::state::GameState;
+
+use super::{Health, MaxHealth};
 
 
 const PLAYER_HEIGHT: f32 = 1.0;
@@ -20,6 +26,7 @@ const MOVEMENT_SPEED: f32 = 16.0;
 const JUMP_HEIGHT: f32 = 5.0;
 const FLOATING_HEIGHT: f32 = 0.1;
 const INTERACT_RAY_TIME: f32 = 1.0; //how long ray can travel, longer time equals longer distance
+const PLAYER_HEALTH: f32 = 100.0;
 
 pub struct PlayerPlugin;
 
@@ -132,16 +139,21 @@ impl Trigger for IsJumping {
 fn add_player(
     mut commands: Commands,
     player: Query<Entity, Added<Player>>,
+    layer_matrix: Res<CollisionLayerMatrix>,
 ) {
     for entity in player.iter() {
         commands
             .entity(entity)
             .insert(Name::new("Player"))
             .insert(CurrentState::new(PlayerStates::Idle))
+            .insert((Health(PLAYER_HEALTH), MaxHealth(PLAYER_HEALTH)))
             .insert((
                 RigidBody::Dynamic,
             ))
             .insert(LockedAxes::new().lock_rotation_x().lock_rotation_z())
+            .insert(CollisionLayersProxy(vec![GameLayer::Player]))
+            // followed by the main camera
+            .insert(CameraTrackable)
             .insert(TnuaControllerBundle::default())
             .insert(player_state_machine(entity))
             .with_children(|children| {
@@ -156,7 +168,9 @@ fn add_player(
                     RayCaster::new(Vec3::ZERO, Vec3::Z)
                         .with_max_time_of_impact(INTERACT_RAY_TIME)
                         .with_query_filter(
-                            SpatialQueryFilter::new().without_entities([builder.parent_entity()]),
+                            layer_matrix
+                                .query_filter(GameLayer::Player)
+                                .without_entities([builder.parent_entity()]),
                         ),
                     SpatialBundle::default(),
                 ));
@@ -224,8 +238,10 @@ fn build_movement(app: &mut App) {
     .add_systems(
         FixedUpdate,
         (player_jumping, player_movement_walk)
-            .in_set(TnuaUserControlsSystemSet),
-    );
+            .in_set(TnuaUserControlsSystemSet)
+            .run_if(in_state(GameState::InGame)),
+    )
+    .add_systems(Update, player_controls_enabled.pipe(toggle_player_actions));
     //.add_systems(Update, player_animation);
 }
 
@@ -270,6 +286,16 @@ fn add_action_state(mut entity: EntityCommands) {
     });
 }
 
+// the movement keys & interaction are shared with the strategy camera
+fn toggle_player_actions(
+    enabled: In<bool>,
+    mut toggle_actions: ResMut<ToggleActions<Action>>,
+) {
+    if toggle_actions.enabled != enabled.0 {
+        toggle_actions.enabled = enabled.0;
+    }
+}
+
 fn player_jumping(
     mut player: Query<
         (
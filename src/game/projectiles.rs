@@ -0,0 +1,448 @@
+use bevy::prelude::*;
+use bevy::utils::HashSet;
+use bevy_gltf_blueprints::{BluePrintBundle, BlueprintName, GameWorldTag};
+use bevy_xpbd_3d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    core::{find_in_ancestors, CollisionLayerMatrix, CollisionLayersProxy, GameLayer},
+    state::GameState,
+};
+
+use super::{level_root, DamageEvent, DamageKind, Dying, Enemy, Tower, TowerFired};
+
+// seconds before a projectile that did not hit anything gets despawned
+const PROJECTILE_LIFETIME: f32 = 5.0;
+// homing projectiles closer than this to their target count as a hit, even without colliders
+const HOMING_HIT_DISTANCE: f32 = 0.5;
+// where the projectiles come from for towers without a Muzzle
+const DEFAULT_MUZZLE_OFFSET: Vec3 = Vec3::new(0.0, 1.5, 0.0);
+
+#[derive(Reflect, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectileMode {
+    /// flies straight at its target, following it
+    #[default]
+    Homing,
+    /// thrown in an arc towards where the target was, subject to gravity
+    Ballistic,
+    /// instant hit, via a ray cast: no projectile is spawned at all
+    Hitscan,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Marker for the point (inside a tower blueprint) projectiles are spawned at
+pub struct Muzzle;
+
+#[derive(Component, Debug)]
+pub struct Projectile {
+    /// the tower that fired it
+    pub source: Entity,
+    pub target: Entity,
+    pub damage: f32,
+    pub kind: DamageKind,
+    pub splash_radius: f32,
+    pub mode: ProjectileMode,
+    pub speed: f32,
+    pub lifetime: Timer,
+}
+
+// damages whatever got hit, or everything in range for splash damage
+fn impact(
+    position: Vec3,
+    hit: Option<Entity>,
+    projectile: &Projectile,
+    enemies: &Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dying>)>,
+    damage_events: &mut EventWriter<DamageEvent>,
+) {
+    let mut damage = |target: Entity| {
+        damage_events.send(DamageEvent {
+            target,
+            amount: projectile.damage,
+            kind: projectile.kind,
+            source: Some(projectile.source),
+        })
+    };
+
+    if projectile.splash_radius > 0.0 {
+        for (enemy, enemy_transform) in enemies.iter() {
+            if enemy_transform.translation().distance(position) <= projectile.splash_radius {
+                damage(enemy);
+            }
+        }
+    } else if let Some(hit) = hit {
+        damage(hit);
+    }
+}
+
+fn muzzle_position(
+    tower: Entity,
+    tower_transform: &GlobalTransform,
+    muzzles: &Query<(Entity, &GlobalTransform), With<Muzzle>>,
+    parents: &Query<&Parent>,
+) -> Vec3 {
+    muzzles
+        .iter()
+        .find(|(muzzle, _)| find_in_ancestors(*muzzle, parents, |entity| entity == tower).is_some())
+        .map(|(_, muzzle_transform)| muzzle_transform.translation())
+        .unwrap_or_else(|| tower_transform.transform_point(DEFAULT_MUZZLE_OFFSET))
+}
+
+pub fn fire_projectiles(
+    mut tower_fired_events: EventReader<TowerFired>,
+    towers: Query<(&Tower, &GlobalTransform)>,
+    muzzles: Query<(Entity, &GlobalTransform), With<Muzzle>>,
+    parents: Query<&Parent>,
+    enemies: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dying>)>,
+    game_world: Query<&Children, With<GameWorldTag>>,
+    spatial_query: SpatialQuery,
+    layer_matrix: Res<CollisionLayerMatrix>,
+    gravity: Res<Gravity>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    for TowerFired { tower, target } in tower_fired_events.read() {
+        let Ok((tower_settings, tower_transform)) = towers.get(*tower) else {
+            continue;
+        };
+        let Ok((_, target_transform)) = enemies.get(*target) else {
+            continue;
+        };
+        let muzzle = muzzle_position(*tower, tower_transform, &muzzles, &parents);
+        let to_target = target_transform.translation() - muzzle;
+
+        let projectile = Projectile {
+            source: *tower,
+            target: *target,
+            damage: tower_settings.damage,
+            kind: tower_settings.damage_kind,
+            splash_radius: tower_settings.splash_radius,
+            mode: tower_settings.projectile_mode,
+            speed: tower_settings.projectile_speed,
+            lifetime: Timer::from_seconds(PROJECTILE_LIFETIME, TimerMode::Once),
+        };
+
+        // no blueprint means nothing to show, so treat it like a hitscan
+        if projectile.mode == ProjectileMode::Hitscan || tower_settings.projectile.is_empty() {
+            let hit = spatial_query.cast_ray(
+                muzzle,
+                to_target.normalize_or_zero(),
+                tower_settings.range * 1.5,
+                true,
+                layer_matrix
+                    .query_filter(GameLayer::Projectile)
+                    .without_entities([*tower]),
+            );
+            if let Some(hit) = hit {
+                let enemy =
+                    find_in_ancestors(hit.entity, &parents, |entity| enemies.contains(entity));
+                let position = muzzle + to_target.normalize_or_zero() * hit.time_of_impact;
+                impact(position, enemy, &projectile, &enemies, &mut damage_events);
+            }
+            continue;
+        }
+
+        let (rigid_body, velocity) = match projectile.mode {
+            ProjectileMode::Ballistic => {
+                // solve for the initial velocity reaching the target in `flight_time`, given gravity
+                let flight_time = (to_target.length() / projectile.speed.max(0.1)).max(0.1);
+                let velocity =
+                    (to_target - 0.5 * gravity.0 * flight_time * flight_time) / flight_time;
+                (RigidBody::Dynamic, velocity)
+            }
+            _ => (
+                RigidBody::Kinematic,
+                to_target.normalize_or_zero() * projectile.speed,
+            ),
+        };
+
+        let Some(level_root) = level_root(&game_world) else {
+            warn!("no level to spawn the projectile of {:?} in", tower);
+            continue;
+        };
+        let new_entity = commands
+            .spawn((
+                BluePrintBundle {
+                    blueprint: BlueprintName(tower_settings.projectile.clone()),
+                    transform: TransformBundle::from_transform(
+                        Transform::from_translation(muzzle).looking_to(to_target, Vec3::Y),
+                    ),
+                    ..Default::default()
+                },
+                Name::from(format!("{}_projectile", tower_settings.projectile)),
+                projectile,
+                CollisionLayersProxy(vec![GameLayer::Projectile]),
+                rigid_body,
+                LinearVelocity(velocity),
+                AngularVelocity::ZERO,
+            ))
+            .id();
+        commands.entity(level_root).add_child(new_entity);
+    }
+}
+
+pub fn steer_homing_projectiles(
+    mut projectiles: Query<(&Projectile, &GlobalTransform, &mut LinearVelocity)>,
+    targets: Query<&GlobalTransform>,
+) {
+    for (projectile, transform, mut velocity) in projectiles.iter_mut() {
+        if projectile.mode != ProjectileMode::Homing {
+            continue;
+        }
+        // if the target is gone we just keep flying straight until the lifetime runs out
+        if let Ok(target_transform) = targets.get(projectile.target) {
+            let to_target = target_transform.translation() - transform.translation();
+            velocity.0 = to_target.normalize_or_zero() * projectile.speed;
+        }
+    }
+}
+
+pub fn resolve_projectile_hits(
+    time: Res<Time>,
+    mut collision_started_events: EventReader<CollisionStarted>,
+    mut projectiles: Query<(Entity, &mut Projectile, &GlobalTransform)>,
+    targets: Query<&GlobalTransform>,
+    enemies: Query<(Entity, &GlobalTransform), (With<Enemy>, Without<Dying>)>,
+    parents: Query<&Parent>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut commands: Commands,
+) {
+    let mut finished: HashSet<Entity> = HashSet::new();
+
+    for CollisionStarted(entity1, entity2) in collision_started_events.read() {
+        for (collider, other) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let Some(projectile_entity) =
+                find_in_ancestors(collider, &parents, |entity| projectiles.contains(entity))
+            else {
+                continue;
+            };
+            if finished.contains(&projectile_entity) {
+                continue;
+            }
+            let Ok((_, projectile, transform)) = projectiles.get(projectile_entity) else {
+                continue;
+            };
+            // towers are not hit by their own projectiles, neither are other projectiles
+            if find_in_ancestors(other, &parents, |entity| {
+                entity == projectile.source || projectiles.contains(entity)
+            })
+            .is_some()
+            {
+                continue;
+            }
+
+            let enemy = find_in_ancestors(other, &parents, |entity| enemies.contains(entity));
+            impact(
+                transform.translation(),
+                enemy,
+                projectile,
+                &enemies,
+                &mut damage_events,
+            );
+            finished.insert(projectile_entity);
+        }
+    }
+
+    for (entity, mut projectile, transform) in projectiles.iter_mut() {
+        if finished.contains(&entity) {
+            continue;
+        }
+        if projectile.mode == ProjectileMode::Homing {
+            if let Ok(target_transform) = targets.get(projectile.target) {
+                if target_transform
+                    .translation()
+                    .distance(transform.translation())
+                    < HOMING_HIT_DISTANCE
+                {
+                    let target = enemies
+                        .contains(projectile.target)
+                        .then_some(projectile.target);
+                    impact(
+                        transform.translation(),
+                        target,
+                        &projectile,
+                        &enemies,
+                        &mut damage_events,
+                    );
+                    finished.insert(entity);
+                    continue;
+                }
+            }
+        }
+        if projectile.lifetime.tick(time.delta()).finished() {
+            finished.insert(entity);
+        }
+    }
+
+    for entity in finished {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+pub struct ProjectilesPlugin;
+impl Plugin for ProjectilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<ProjectileMode>()
+            .register_type::<Muzzle>()
+            .add_systems(
+                Update,
+                (
+                    fire_projectiles,
+                    steer_homing_projectiles,
+                    resolve_projectile_hits,
+                )
+                    .chain()
+                    .after(super::tower_firing)
+                    .run_if(in_state(GameState::InGame)),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StatePlugin;
+
+    fn projectiles_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin, ProjectilesPlugin))
+            .add_event::<TowerFired>()
+            .add_event::<DamageEvent>()
+            .add_event::<CollisionStarted>()
+            .init_resource::<SpatialQueryPipeline>()
+            .init_resource::<CollisionLayerMatrix>()
+            .init_resource::<Gravity>();
+        app.world.spawn(GameWorldTag).with_children(|world| {
+            world.spawn((Name::new("Ground"), TransformBundle::default()));
+        });
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        app.update();
+        app
+    }
+
+    fn projectile(source: Entity, target: Entity, splash_radius: f32) -> Projectile {
+        Projectile {
+            source,
+            target,
+            damage: 10.0,
+            kind: DamageKind::Physical,
+            splash_radius,
+            mode: ProjectileMode::Homing,
+            speed: 4.0,
+            lifetime: Timer::from_seconds(PROJECTILE_LIFETIME, TimerMode::Once),
+        }
+    }
+
+    fn spawn_enemy(app: &mut App, position: Vec3) -> Entity {
+        app.world
+            .spawn((Enemy, GlobalTransform::from_translation(position)))
+            .id()
+    }
+
+    fn damaged(app: &App) -> Vec<Entity> {
+        let mut reader = app.world.resource::<Events<DamageEvent>>().get_reader();
+        reader
+            .read(app.world.resource::<Events<DamageEvent>>())
+            .map(|damage| damage.target)
+            .collect()
+    }
+
+    #[test]
+    fn homing_projectiles_chase_their_target_until_they_hit_it() {
+        let mut app = projectiles_app();
+        let tower = app.world.spawn_empty().id();
+        let target = spawn_enemy(&mut app, Vec3::new(0.0, 0.0, 0.3));
+        let close = app
+            .world
+            .spawn((
+                projectile(tower, target, 0.0),
+                GlobalTransform::default(),
+                LinearVelocity::ZERO,
+            ))
+            .id();
+        let far = app
+            .world
+            .spawn((
+                projectile(tower, target, 0.0),
+                GlobalTransform::from_translation(Vec3::new(0.0, 0.0, 10.3)),
+                LinearVelocity::ZERO,
+            ))
+            .id();
+        app.update();
+
+        assert_eq!(damaged(&app), vec![target]);
+        assert!(app.world.get_entity(close).is_none());
+        let velocity = app.world.get::<LinearVelocity>(far).unwrap();
+        assert!(velocity.0.abs_diff_eq(Vec3::new(0.0, 0.0, -4.0), 1e-5));
+    }
+
+    #[test]
+    fn splash_damage_hits_every_enemy_in_range() {
+        let mut app = projectiles_app();
+        let tower = app.world.spawn_empty().id();
+        let target = spawn_enemy(&mut app, Vec3::new(1.0, 0.0, 0.0));
+        let nearby = spawn_enemy(&mut app, Vec3::new(-1.5, 0.0, 0.0));
+        spawn_enemy(&mut app, Vec3::new(5.0, 0.0, 0.0));
+        let dying = spawn_enemy(&mut app, Vec3::ZERO);
+        app.world
+            .entity_mut(dying)
+            .insert(Dying(Timer::from_seconds(1.0, TimerMode::Once)));
+        let mut shell = projectile(tower, target, 2.0);
+        shell.mode = ProjectileMode::Ballistic;
+        let shell = app
+            .world
+            .spawn((shell, GlobalTransform::default(), LinearVelocity::ZERO))
+            .id();
+        let ground = app
+            .world
+            .query_filtered::<Entity, With<Name>>()
+            .single(&app.world);
+        app.world.send_event(CollisionStarted(ground, shell));
+        app.update();
+
+        let mut damaged = damaged(&app);
+        damaged.sort();
+        let mut expected = vec![target, nearby];
+        expected.sort();
+        assert_eq!(damaged, expected);
+        assert!(app.world.get_entity(shell).is_none());
+    }
+
+    #[test]
+    fn ballistic_projectiles_are_thrown_to_land_on_their_target() {
+        let mut app = projectiles_app();
+        let tower = app
+            .world
+            .spawn((
+                Tower {
+                    projectile: "Arrow".into(),
+                    projectile_mode: ProjectileMode::Ballistic,
+                    projectile_speed: 10.0,
+                    ..default()
+                },
+                GlobalTransform::default(),
+            ))
+            .id();
+        let target_position = Vec3::new(10.0, 0.0, 0.0);
+        let target = spawn_enemy(&mut app, target_position);
+        app.world.send_event(TowerFired { tower, target });
+        app.update();
+
+        let (transform, velocity, parent) = app
+            .world
+            .query_filtered::<(&Transform, &LinearVelocity, &Parent), With<Projectile>>()
+            .single(&app.world);
+        let muzzle = transform.translation;
+        assert_eq!(muzzle, DEFAULT_MUZZLE_OFFSET);
+        let flight_time = (target_position - muzzle).length() / 10.0;
+        let gravity = app.world.resource::<Gravity>().0;
+        let landing = muzzle + velocity.0 * flight_time + 0.5 * gravity * flight_time * flight_time;
+        assert!(landing.abs_diff_eq(target_position, 1e-4));
+        assert!(app
+            .world
+            .get::<Name>(parent.get())
+            .is_some_and(|name| name.as_str() == "Ground"));
+    }
+}
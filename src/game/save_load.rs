@@ -0,0 +1,632 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::ecs::system::SystemState;
+use bevy::hierarchy::despawn_with_children_recursive;
+use bevy::prelude::*;
+use bevy::reflect::serde::{ReflectSerializer, UntypedReflectDeserializer};
+use bevy::scene::SceneInstanceReady;
+use bevy_gltf_blueprints::{
+    BluePrintBundle, BlueprintName, GameWorldTag, GltfBlueprintsSet, SpawnHere,
+};
+use bevy_xpbd_3d::prelude::*;
+use serde::de::DeserializeSeed;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::{CoreAssets, GameAssets},
+    core::find_in_ancestors,
+    state::{AppState, GameState},
+};
+
+use super::{
+    current_level_definition, level_index, level_root, CurrentLevel, Dying, LevelCatalog, Wallet,
+    WaveProgress, WaveSpawner, WavesDefinition,
+};
+
+/// bump this whenever the layout of `SaveFile` changes, & add a step to `migrate`
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVES_FOLDER: &str = "saves";
+pub const QUICKSAVE: &str = "quicksave";
+// only our own components get saved, everything else comes back with the blueprints
+const SAVED_TYPE_PATH_PREFIX: &str = "td3::";
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Marker for entities that get saved & restored, they need a BlueprintName to be respawned
+pub struct Persist;
+
+#[derive(Event, Debug)]
+pub struct SaveRequest {
+    /// name of the save, without folder or extension
+    pub path: String,
+}
+
+#[derive(Event, Debug)]
+pub struct LoadRequest {
+    /// name of the save, without folder or extension
+    pub path: String,
+}
+
+/// A save to load as soon as the level is ready, ie when loading from the main menu
+#[derive(Resource, Debug)]
+pub struct PendingLoad(pub String);
+
+/// The file being written/read while in GameState::InSaving / GameState::InLoading
+#[derive(Resource, Debug)]
+pub struct SaveLoadTarget {
+    /// name of the save, without folder or extension
+    pub name: String,
+    /// the state to go back to once done
+    pub resume_to: GameState,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveFile {
+    pub version: u32,
+    /// name of the level in the level catalog
+    pub level: String,
+    pub wave_index: usize,
+    /// where the spawner was in the current wave
+    pub wave_progress: WaveProgress,
+    pub gold: u32,
+    pub entities: Vec<SavedEntity>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedEntity {
+    pub blueprint: String,
+    pub name: Option<String>,
+    pub translation: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    #[serde(default)]
+    pub linear_velocity: Option<[f32; 3]>,
+    #[serde(default)]
+    pub angular_velocity: Option<[f32; 3]>,
+    /// reflected components, each one serialized to RON
+    #[serde(default)]
+    pub components: Vec<String>,
+}
+
+#[derive(Component)]
+/// Components read from a save: they get inserted once the blueprint is done spawning, so that the blueprint's own components do not overwrite them
+pub struct SavedComponents {
+    components: Vec<Box<dyn Reflect>>,
+}
+
+pub fn save_path(name: &str) -> PathBuf {
+    Path::new(SAVES_FOLDER).join(format!("{}.save.ron", name))
+}
+
+/// Names of the saves in the saves folder, most recent first
+pub fn list_saves() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(SAVES_FOLDER) else {
+        return vec![];
+    };
+    let mut saves: Vec<(String, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_suffix(".save.ron")?.to_string();
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()?;
+            Some((name, modified))
+        })
+        .collect();
+    saves.sort_by(|(_, a), (_, b)| b.cmp(a));
+    saves.into_iter().map(|(name, _)| name).collect()
+}
+
+// upgrades older saves to the current layout: there are none yet, older versions get a step here once they exist
+fn migrate(save: SaveFile) -> Result<SaveFile, String> {
+    match save.version {
+        SAVE_VERSION => Ok(save),
+        version if version > SAVE_VERSION => Err(format!(
+            "save version {} is newer than the supported version {}",
+            version, SAVE_VERSION
+        )),
+        version => Err(format!("no migration from save version {}", version)),
+    }
+}
+
+type LevelHierarchy<'w, 's> = (
+    Query<'w, 's, &'static Parent>,
+    Query<'w, 's, (), With<GameWorldTag>>,
+);
+
+fn in_game_world(entity: Entity, (parents, game_worlds): &LevelHierarchy) -> bool {
+    find_in_ancestors(entity, parents, |entity| game_worlds.contains(entity)).is_some()
+}
+
+fn collect_save(world: &mut World) -> SaveFile {
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+
+    let mut persistent = world.query_filtered::<(
+        Entity,
+        Option<&BlueprintName>,
+        Option<&Name>,
+        &Transform,
+        Option<&LinearVelocity>,
+        Option<&AngularVelocity>,
+    ), (With<Persist>, Without<Dying>)>();
+    let mut level_hierarchy = SystemState::<LevelHierarchy>::new(world);
+    let level_hierarchy = level_hierarchy.get(world);
+
+    let mut entities = vec![];
+    for (entity, blueprint, name, transform, linear_velocity, angular_velocity) in
+        persistent.iter(world)
+    {
+        if !in_game_world(entity, &level_hierarchy) {
+            continue;
+        }
+        let Some(blueprint) = blueprint else {
+            warn!(
+                "{:?} ({:?}) is marked as Persist but has no BlueprintName, it cannot be saved",
+                entity, name
+            );
+            continue;
+        };
+
+        let entity_ref = world.entity(entity);
+        let mut components = vec![];
+        for component_id in entity_ref.archetype().components() {
+            let Some(registration) = world
+                .components()
+                .get_info(component_id)
+                .and_then(|info| info.type_id())
+                .and_then(|type_id| registry.get(type_id))
+            else {
+                continue;
+            };
+            if !registration
+                .type_info()
+                .type_path()
+                .starts_with(SAVED_TYPE_PATH_PREFIX)
+            {
+                continue;
+            }
+            let Some(component) = registration
+                .data::<ReflectComponent>()
+                .and_then(|reflect_component| reflect_component.reflect(entity_ref))
+            else {
+                continue;
+            };
+            match ron::to_string(&ReflectSerializer::new(component, &registry)) {
+                Ok(serialized) => components.push(serialized),
+                Err(error) => warn!(
+                    "could not save {} of {:?}: {}",
+                    registration.type_info().type_path(),
+                    entity,
+                    error
+                ),
+            }
+        }
+
+        entities.push(SavedEntity {
+            blueprint: blueprint.0.clone(),
+            name: name.map(|name| name.to_string()),
+            translation: transform.translation.to_array(),
+            rotation: transform.rotation.to_array(),
+            scale: transform.scale.to_array(),
+            linear_velocity: linear_velocity.map(|velocity| velocity.0.to_array()),
+            angular_velocity: angular_velocity.map(|velocity| velocity.0.to_array()),
+            components,
+        });
+    }
+
+    let level = current_level_definition(
+        world.resource::<CoreAssets>(),
+        world.resource::<Assets<LevelCatalog>>(),
+        world.resource::<CurrentLevel>(),
+    )
+    .map(|level| level.name.clone())
+    .unwrap_or_default();
+
+    SaveFile {
+        version: SAVE_VERSION,
+        level,
+        wave_index: world.resource::<WaveSpawner>().current,
+        wave_progress: world.resource::<WaveSpawner>().progress(),
+        gold: world.resource::<Wallet>().gold,
+        entities,
+    }
+}
+
+fn write_save(world: &mut World, name: &str) -> Result<usize, String> {
+    let save = collect_save(world);
+    let path = save_path(name);
+    let content = ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default())
+        .map_err(|error| error.to_string())?;
+    if let Some(folder) = path.parent() {
+        fs::create_dir_all(folder).map_err(|error| error.to_string())?;
+    }
+    fs::write(&path, content).map_err(|error| error.to_string())?;
+    Ok(save.entities.len())
+}
+
+pub fn read_save(name: &str) -> Result<SaveFile, String> {
+    let content = fs::read_to_string(save_path(name)).map_err(|error| error.to_string())?;
+    let save: SaveFile = ron::from_str(&content).map_err(|error| error.to_string())?;
+    migrate(save)
+}
+
+fn restore_save(world: &mut World, save: SaveFile) {
+    let mut game_worlds = SystemState::<Query<&Children, With<GameWorldTag>>>::new(world);
+    let Some(level_root) = level_root(&game_worlds.get(world)) else {
+        warn!("cannot load a save without a level");
+        return;
+    };
+
+    // out with the current state of the level...
+    let mut persistent = world.query_filtered::<Entity, With<Persist>>();
+    let mut level_hierarchy = SystemState::<LevelHierarchy>::new(world);
+    let level_hierarchy = level_hierarchy.get(world);
+    let stale: Vec<Entity> = persistent
+        .iter(world)
+        .filter(|entity| in_game_world(*entity, &level_hierarchy))
+        .collect();
+    for entity in stale {
+        despawn_with_children_recursive(world, entity);
+    }
+
+    // ...and in with the saved one
+    world.resource_mut::<Wallet>().gold = save.gold;
+    let waves = world.resource::<GameAssets>().waves.clone();
+    let wave = world
+        .resource::<Assets<WavesDefinition>>()
+        .get(&waves)
+        .and_then(|definition| definition.waves.get(save.wave_index));
+    let spawner = WaveSpawner::resuming(save.wave_index, wave, &save.wave_progress);
+    world.insert_resource(spawner);
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    for saved in save.entities {
+        let mut components = vec![];
+        for component in saved.components.iter() {
+            let result = ron::Deserializer::from_str(component)
+                .map_err(|error| error.to_string())
+                .and_then(|mut deserializer| {
+                    UntypedReflectDeserializer::new(&registry)
+                        .deserialize(&mut deserializer)
+                        .map_err(|error| error.to_string())
+                });
+            match result {
+                Ok(component) => components.push(component),
+                Err(error) => warn!(
+                    "could not load a component of {}: {}",
+                    saved.blueprint, error
+                ),
+            }
+        }
+
+        let transform = Transform {
+            translation: Vec3::from_array(saved.translation),
+            rotation: Quat::from_array(saved.rotation),
+            scale: Vec3::from_array(saved.scale),
+        };
+        let mut entity = world.spawn((
+            BluePrintBundle {
+                blueprint: BlueprintName(saved.blueprint.clone()),
+                transform: TransformBundle::from_transform(transform),
+                ..Default::default()
+            },
+            Name::from(saved.name.unwrap_or(saved.blueprint)),
+            Persist,
+            SavedComponents { components },
+        ));
+        if let Some(velocity) = saved.linear_velocity {
+            entity.insert(LinearVelocity(Vec3::from_array(velocity)));
+        }
+        if let Some(velocity) = saved.angular_velocity {
+            entity.insert(AngularVelocity(Vec3::from_array(velocity)));
+        }
+        let entity = entity.id();
+        world.entity_mut(level_root).add_child(entity);
+    }
+}
+
+// turns save/load requests into GameState::InSaving / GameState::InLoading
+pub fn handle_save_load_requests(
+    mut save_requests: EventReader<SaveRequest>,
+    mut load_requests: EventReader<LoadRequest>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let target = |path: &str| SaveLoadTarget {
+        name: path.to_string(),
+        resume_to: *game_state.get(),
+    };
+    if let Some(SaveRequest { path }) = save_requests.read().last() {
+        commands.insert_resource(target(path));
+        next_game_state.set(GameState::InSaving);
+    } else if let Some(LoadRequest { path }) = load_requests.read().last() {
+        commands.insert_resource(target(path));
+        next_game_state.set(GameState::InLoading);
+    }
+}
+
+pub fn quick_save_load_input(
+    keycode: Res<Input<KeyCode>>,
+    mut save_requests: EventWriter<SaveRequest>,
+    mut load_requests: EventWriter<LoadRequest>,
+) {
+    if keycode.just_pressed(KeyCode::F5) {
+        save_requests.send(SaveRequest {
+            path: QUICKSAVE.into(),
+        });
+    }
+    if keycode.just_pressed(KeyCode::F9) {
+        load_requests.send(LoadRequest {
+            path: QUICKSAVE.into(),
+        });
+    }
+}
+
+// loads the pending save once the level's scene is there, so its own persistent entities can be replaced
+pub fn load_pending_save(
+    mut ready_events: EventReader<SceneInstanceReady>,
+    game_worlds: Query<(), With<GameWorldTag>>,
+    pending: Option<Res<PendingLoad>>,
+    mut load_requests: EventWriter<LoadRequest>,
+    mut commands: Commands,
+) {
+    let Some(pending) = pending else {
+        return;
+    };
+    if ready_events
+        .read()
+        .any(|SceneInstanceReady { parent }| game_worlds.contains(*parent))
+    {
+        load_requests.send(LoadRequest {
+            path: pending.0.clone(),
+        });
+        commands.remove_resource::<PendingLoad>();
+    }
+}
+
+pub fn save_game(world: &mut World) {
+    let resume_to = match world.remove_resource::<SaveLoadTarget>() {
+        Some(SaveLoadTarget { name, resume_to }) => {
+            match write_save(world, &name) {
+                Ok(count) => info!("saved {} entities to {}", count, name),
+                Err(error) => warn!("could not save to {}: {}", name, error),
+            }
+            resume_to
+        }
+        None => GameState::InGame,
+    };
+    world.resource_mut::<NextState<GameState>>().set(resume_to);
+}
+
+pub fn load_game(world: &mut World) {
+    let resume_to = match world.remove_resource::<SaveLoadTarget>() {
+        Some(SaveLoadTarget { name, resume_to }) => match read_save(&name) {
+            Ok(save) => {
+                let catalog = world
+                    .resource::<Assets<LevelCatalog>>()
+                    .get(&world.resource::<CoreAssets>().levels);
+                let level = level_index(catalog, &save.level);
+                if level == world.resource::<CurrentLevel>().0 {
+                    info!("loading {} entities from {}", save.entities.len(), name);
+                    restore_save(world, save);
+                    resume_to
+                } else {
+                    // the save gets loaded once the right level is ready
+                    info!(
+                        "{} is a save of level {}, switching to it",
+                        name, save.level
+                    );
+                    world.insert_resource(CurrentLevel(level));
+                    world.insert_resource(PendingLoad(name));
+                    world
+                        .resource_mut::<NextState<AppState>>()
+                        .set(AppState::AppLoading);
+                    GameState::None
+                }
+            }
+            Err(error) => {
+                warn!("could not load {}: {}", name, error);
+                resume_to
+            }
+        },
+        None => GameState::InGame,
+    };
+    world.resource_mut::<NextState<GameState>>().set(resume_to);
+}
+
+// the blueprint's components get copied over when it is done spawning (& loses its SpawnHere), the saved ones must come after that
+pub fn apply_saved_components(world: &mut World) {
+    let mut saved = world.query_filtered::<Entity, (With<SavedComponents>, Without<SpawnHere>)>();
+    let ready: Vec<Entity> = saved.iter(world).collect();
+    if ready.is_empty() {
+        return;
+    }
+
+    let registry = world.resource::<AppTypeRegistry>().clone();
+    let registry = registry.read();
+    for entity in ready {
+        let mut entity = world.entity_mut(entity);
+        let Some(saved) = entity.take::<SavedComponents>() else {
+            continue;
+        };
+        for component in saved.components.iter() {
+            let Some(reflect_component) = component
+                .get_represented_type_info()
+                .and_then(|info| registry.get(info.type_id()))
+                .and_then(|registration| registration.data::<ReflectComponent>())
+            else {
+                warn!(
+                    "cannot restore {}, it is not a registered component",
+                    component.reflect_type_path()
+                );
+                continue;
+            };
+            reflect_component.insert(&mut entity, component.as_ref());
+        }
+    }
+}
+
+pub struct SaveLoadPlugin;
+impl Plugin for SaveLoadPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Persist>()
+            .add_event::<SaveRequest>()
+            .add_event::<LoadRequest>()
+            .add_systems(OnEnter(GameState::InSaving), save_game)
+            .add_systems(OnEnter(GameState::InLoading), load_game)
+            .add_systems(
+                Update,
+                (
+                    apply_saved_components.after(GltfBlueprintsSet::AfterSpawn),
+                    (quick_save_load_input, load_pending_save)
+                        .run_if(in_state(GameState::InGame))
+                        .before(handle_save_load_requests),
+                    // saving is also possible from the pause menu
+                    handle_save_load_requests
+                        .run_if(in_state(GameState::InGame).or_else(in_state(GameState::Paused))),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Health, WaveDefinition, WaveSpawnerStatus};
+
+    fn level_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<LevelCatalog>()
+            .init_asset::<WavesDefinition>()
+            .register_type::<Health>();
+
+        let levels = app.world.resource_mut::<Assets<LevelCatalog>>().add(
+            ron::from_str::<LevelCatalog>(
+                r#"(levels: [(name: "Level 1", world: "models/World.glb", waves: "levels/level1.waves.ron")])"#,
+            )
+            .unwrap(),
+        );
+        let waves = app.world.resource_mut::<Assets<WavesDefinition>>().add(
+            ron::from_str::<WavesDefinition>(
+                r#"(waves: [
+                    (enemy: "Fox", count: 3, interval: 1.0, spawn_point: "EnemySpawn", path: "EnemyPath",
+                        delay: 2.0, speed: 1.0, health: 10.0),
+                    (enemy: "Fox", count: 5, interval: 2.0, spawn_point: "EnemySpawn", path: "EnemyPath",
+                        delay: 4.0, speed: 1.0, health: 20.0),
+                ])"#,
+            )
+            .unwrap(),
+        );
+        app.insert_resource(CoreAssets { levels })
+            .insert_resource(GameAssets {
+                world: Handle::default(),
+                models: default(),
+                waves,
+                towers: Handle::default(),
+                materials: Handle::default(),
+                sounds: Handle::default(),
+            })
+            .init_resource::<CurrentLevel>()
+            .init_resource::<Wallet>()
+            .init_resource::<WaveSpawner>();
+        app.world.spawn(GameWorldTag).with_children(|world| {
+            world.spawn((Name::new("Ground"), TransformBundle::default()));
+        });
+        app
+    }
+
+    fn wave(app: &App, index: usize) -> WaveDefinition {
+        let waves = &app.world.resource::<GameAssets>().waves;
+        app.world
+            .resource::<Assets<WavesDefinition>>()
+            .get(waves)
+            .unwrap()
+            .waves[index]
+            .clone()
+    }
+
+    #[test]
+    fn saves_round_trip_the_level_state() {
+        let mut app = level_app();
+        let wave = wave(&app, 1);
+        let progress = WaveProgress {
+            status: WaveSpawnerStatus::Spawning,
+            spawned: 2,
+            elapsed: 0.5,
+        };
+        app.insert_resource(Wallet { gold: 120 })
+            .insert_resource(WaveSpawner::resuming(1, Some(&wave), &progress));
+        let transform = Transform::from_xyz(1.0, 2.0, 3.0).with_scale(Vec3::splat(2.0));
+        let level_root = app
+            .world
+            .query_filtered::<&Children, With<GameWorldTag>>()
+            .single(&app.world)[0];
+        let tower = app
+            .world
+            .spawn((
+                BlueprintName("Pillar".into()),
+                Name::new("Tower"),
+                transform,
+                Persist,
+                Health(7.0),
+            ))
+            .id();
+        app.world.entity_mut(level_root).add_child(tower);
+
+        let content = ron::to_string(&collect_save(&mut app.world)).unwrap();
+        let save = migrate(ron::from_str(&content).unwrap()).unwrap();
+        assert_eq!(save.level, "Level 1");
+
+        // everything changes before loading
+        app.insert_resource(Wallet { gold: 0 })
+            .insert_resource(WaveSpawner::starting_at(0, None));
+        app.world.entity_mut(tower).insert(Health(1.0));
+        restore_save(&mut app.world, save);
+
+        assert_eq!(app.world.resource::<Wallet>().gold, 120);
+        let spawner = app.world.resource::<WaveSpawner>();
+        assert_eq!(spawner.current, 1);
+        assert_eq!(spawner.progress(), progress);
+
+        let restored = app
+            .world
+            .query_filtered::<Entity, With<Persist>>()
+            .single(&app.world);
+        assert_ne!(restored, tower);
+        assert_eq!(
+            app.world.get::<Parent>(restored).map(Parent::get),
+            Some(level_root)
+        );
+        assert_eq!(
+            app.world.get::<BlueprintName>(restored).unwrap().0,
+            "Pillar"
+        );
+        assert_eq!(*app.world.get::<Transform>(restored).unwrap(), transform);
+
+        // the saved components wait for the blueprint to be done spawning
+        apply_saved_components(&mut app.world);
+        assert!(app.world.get::<Health>(restored).is_none());
+        app.world.entity_mut(restored).remove::<SpawnHere>();
+        apply_saved_components(&mut app.world);
+        assert_eq!(app.world.get::<Health>(restored).unwrap().0, 7.0);
+    }
+
+    #[test]
+    fn saves_from_a_newer_version_are_refused() {
+        let save: SaveFile = ron::from_str(&format!(
+            "(version: {}, level: \"Level 1\", wave_index: 0, \
+             wave_progress: (status: Waiting, spawned: 0, elapsed: 0.0), gold: 0, entities: [])",
+            SAVE_VERSION + 1
+        ))
+        .unwrap();
+        assert!(migrate(save).is_err());
+    }
+}
@@ -0,0 +1,382 @@
+use bevy::audio::Volume;
+use bevy::prelude::*;
+use bevy_tnua::TnuaProximitySensor;
+use bevy_xpbd_3d::prelude::*;
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+
+use crate::{assets::GameAssets, core::find_in_ancestors, state::GameState};
+
+use super::{Player, SoundMaterial};
+
+// collisions softer than this do not make a sound
+const IMPACT_IMPULSE_THRESHOLD: f32 = 2.0;
+// distance from the player's origin to the ground under which its feet are considered to touch it
+const FOOTSTEP_GROUND_DISTANCE: f32 = 0.5;
+// horizontal distance walked between two footsteps
+const FOOTSTEP_STRIDE: f32 = 1.6;
+
+/// What made a sound, each kind has its own entries in the `SoundBank`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundCueKind {
+    Impact,
+    Footstep,
+}
+
+/// Something made a sound: resolved against the `SoundBank` into an actual sound, when playback is enabled
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct SoundCue {
+    pub kind: SoundCueKind,
+    pub material_a: SoundMaterial,
+    pub material_b: SoundMaterial,
+    /// impulse of an impact, speed for footsteps
+    pub intensity: f32,
+    pub position: Vec3,
+}
+
+/// The sounds of a kind of cue on a pair of materials, in any order: `None` matches any material
+#[derive(Deserialize, Debug, Clone)]
+pub struct SoundBankEntry {
+    pub kind: SoundCueKind,
+    pub materials: (SoundMaterial, SoundMaterial),
+    /// paths relative to the assets folder, one of them is picked at random for each cue
+    pub sounds: Vec<String>,
+    #[serde(default = "default_one")]
+    pub volume: f32,
+    /// cues at or above this intensity play at full volume, softer ones proportionally quieter
+    #[serde(default = "default_one")]
+    pub full_intensity: f32,
+}
+
+fn default_one() -> f32 {
+    1.0
+}
+
+/// The sounds of each pair of materials, as described in the `*.sounds.ron` files
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct SoundBank {
+    pub cues: Vec<SoundBankEntry>,
+}
+
+impl SoundBank {
+    /// The entry of this kind for these exact materials, or else the closest one with a `None` wildcard
+    pub fn resolve(
+        &self,
+        kind: SoundCueKind,
+        a: SoundMaterial,
+        b: SoundMaterial,
+    ) -> Option<&SoundBankEntry> {
+        let matches = |entry: &SoundBankEntry, x: SoundMaterial, y: SoundMaterial| {
+            (entry.materials.0 == x && entry.materials.1 == y)
+                || (entry.materials.0 == y && entry.materials.1 == x)
+        };
+        let entries = || self.cues.iter().filter(|entry| entry.kind == kind);
+        entries()
+            .find(|entry| matches(entry, a, b))
+            .or_else(|| {
+                entries().find(|entry| {
+                    matches(entry, a, SoundMaterial::None) || matches(entry, b, SoundMaterial::None)
+                })
+            })
+            .or_else(|| {
+                entries().find(|entry| matches(entry, SoundMaterial::None, SoundMaterial::None))
+            })
+    }
+}
+
+#[derive(Component, Debug, Default)]
+/// Keeps track of the player's steps, to emit footstep cues at each stride
+pub struct Footsteps {
+    travelled: f32,
+    grounded: bool,
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SoundCueSet {
+    Emit,
+    Play,
+}
+
+fn material_of(
+    entity: Entity,
+    materials: &Query<&SoundMaterial>,
+    parents: &Query<&Parent>,
+) -> SoundMaterial {
+    find_in_ancestors(entity, parents, |entity| materials.contains(entity))
+        .and_then(|entity| materials.get(entity).ok())
+        .copied()
+        .unwrap_or_default()
+}
+
+pub fn impact_sound_cues(
+    mut collision_started_events: EventReader<CollisionStarted>,
+    collisions: Res<Collisions>,
+    materials: Query<&SoundMaterial>,
+    parents: Query<&Parent>,
+    transforms: Query<&GlobalTransform>,
+    mut sound_cues: EventWriter<SoundCue>,
+) {
+    for CollisionStarted(entity1, entity2) in collision_started_events.read() {
+        let Some(contacts) = collisions.get(*entity1, *entity2) else {
+            continue;
+        };
+        if contacts.total_normal_impulse < IMPACT_IMPULSE_THRESHOLD {
+            continue;
+        }
+        let material_a = material_of(*entity1, &materials, &parents);
+        let material_b = material_of(*entity2, &materials, &parents);
+        if material_a == SoundMaterial::None && material_b == SoundMaterial::None {
+            continue;
+        }
+
+        // contact points are local to the first collider
+        let Ok(transform) = transforms.get(contacts.entity1) else {
+            continue;
+        };
+        let position = contacts
+            .manifolds
+            .iter()
+            .flat_map(|manifold| manifold.contacts.iter())
+            .next()
+            .map_or(transform.translation(), |contact| {
+                transform.transform_point(contact.point1)
+            });
+        sound_cues.send(SoundCue {
+            kind: SoundCueKind::Impact,
+            material_a,
+            material_b,
+            intensity: contacts.total_normal_impulse,
+            position,
+        });
+    }
+}
+
+pub fn add_footsteps(players: Query<Entity, Added<Player>>, mut commands: Commands) {
+    for player in players.iter() {
+        commands.entity(player).insert(Footsteps::default());
+    }
+}
+
+// the ground under the player is found by the character controller's proximity sensor
+pub fn footstep_sound_cues(
+    time: Res<Time>,
+    mut players: Query<(
+        Entity,
+        &GlobalTransform,
+        &LinearVelocity,
+        &TnuaProximitySensor,
+        &mut Footsteps,
+    )>,
+    materials: Query<&SoundMaterial>,
+    parents: Query<&Parent>,
+    mut sound_cues: EventWriter<SoundCue>,
+) {
+    for (player, transform, velocity, sensor, mut footsteps) in players.iter_mut() {
+        let ground = sensor
+            .output
+            .as_ref()
+            .filter(|output| output.proximity <= FOOTSTEP_GROUND_DISTANCE);
+        let Some(ground) = ground else {
+            footsteps.grounded = false;
+            continue;
+        };
+
+        let cue = |intensity: f32| SoundCue {
+            kind: SoundCueKind::Footstep,
+            material_a: material_of(player, &materials, &parents),
+            material_b: material_of(ground.entity, &materials, &parents),
+            intensity,
+            position: transform.translation(),
+        };
+        if !footsteps.grounded {
+            // landing
+            footsteps.grounded = true;
+            footsteps.travelled = 0.0;
+            sound_cues.send(cue(velocity.y.abs()));
+            continue;
+        }
+
+        let speed = Vec2::new(velocity.x, velocity.z).length();
+        footsteps.travelled += speed * time.delta_seconds();
+        if footsteps.travelled >= FOOTSTEP_STRIDE {
+            footsteps.travelled -= FOOTSTEP_STRIDE;
+            sound_cues.send(cue(speed));
+        }
+    }
+}
+
+pub fn play_sound_cues(
+    mut sound_cues: EventReader<SoundCue>,
+    game_assets: Res<GameAssets>,
+    sound_banks: Res<Assets<SoundBank>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some(sound_bank) = sound_banks.get(&game_assets.sounds) else {
+        return;
+    };
+    let mut rng = rand::thread_rng();
+    for cue in sound_cues.read() {
+        let Some(entry) = sound_bank.resolve(cue.kind, cue.material_a, cue.material_b) else {
+            continue;
+        };
+        let Some(sound) = entry.sounds.choose(&mut rng) else {
+            continue;
+        };
+        let volume = entry.volume * (cue.intensity / entry.full_intensity.max(0.01)).min(1.0);
+        commands.spawn((
+            AudioBundle {
+                source: asset_server.load(sound),
+                settings: PlaybackSettings::DESPAWN
+                    .with_spatial(true)
+                    .with_volume(Volume::new_relative(volume)),
+            },
+            TransformBundle::from_transform(Transform::from_translation(cue.position)),
+            Name::new("SoundCue"),
+        ));
+    }
+}
+
+pub struct SoundCuesPlugin {
+    /// without playback the cues are only emitted, ie for running without an audio device
+    pub playback: bool,
+}
+
+impl Default for SoundCuesPlugin {
+    fn default() -> Self {
+        SoundCuesPlugin { playback: true }
+    }
+}
+
+impl Plugin for SoundCuesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SoundCue>()
+            .configure_sets(Update, SoundCueSet::Emit.before(SoundCueSet::Play))
+            .add_systems(
+                Update,
+                (
+                    add_footsteps,
+                    impact_sound_cues,
+                    footstep_sound_cues.after(add_footsteps),
+                )
+                    .in_set(SoundCueSet::Emit)
+                    .run_if(in_state(GameState::InGame)),
+            );
+        if self.playback {
+            app.add_systems(
+                Update,
+                play_sound_cues
+                    .in_set(SoundCueSet::Play)
+                    .run_if(resource_exists::<GameAssets>()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::ecs::event::ManualEventReader;
+    use bevy::time::TimeUpdateStrategy;
+    use bevy_tnua::TnuaProximitySensorOutput;
+
+    use super::*;
+    use crate::state::StatePlugin;
+
+    #[test]
+    fn walking_players_emit_landing_then_footstep_cues() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            StatePlugin,
+            SoundCuesPlugin { playback: false },
+        ))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            0.5,
+        )));
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+
+        let ground = app.world.spawn(SoundMaterial::Rock).id();
+        app.world.spawn((
+            Player,
+            GlobalTransform::default(),
+            LinearVelocity(Vec3::new(4.0, -3.0, 0.0)),
+            TnuaProximitySensor {
+                output: Some(TnuaProximitySensorOutput {
+                    entity: ground,
+                    proximity: 0.1,
+                    normal: Vec3::Y,
+                    entity_linvel: Vec3::ZERO,
+                    entity_angvel: Vec3::ZERO,
+                }),
+                ..default()
+            },
+        ));
+
+        let mut reader = ManualEventReader::<SoundCue>::default();
+        let mut cues = vec![];
+        for _ in 0..4 {
+            app.update();
+            cues.extend(
+                reader
+                    .read(app.world.resource::<Events<SoundCue>>())
+                    .cloned(),
+            );
+        }
+
+        // landing first, as hard as the fall, then a step each time the player walked a stride
+        assert!(cues.len() >= 2);
+        assert_eq!(
+            cues[0],
+            SoundCue {
+                kind: SoundCueKind::Footstep,
+                material_a: SoundMaterial::None,
+                material_b: SoundMaterial::Rock,
+                intensity: 3.0,
+                position: Vec3::ZERO,
+            }
+        );
+        assert!(cues[1..]
+            .iter()
+            .all(|cue| cue.intensity == 4.0 && cue.material_b == SoundMaterial::Rock));
+    }
+
+    #[test]
+    fn impacts_never_resolve_to_footsteps() {
+        let content = std::fs::read_to_string(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/definitions/default.sounds.ron"
+        ))
+        .unwrap();
+        let bank: SoundBank = ron::from_str(&content).unwrap();
+
+        let impact = bank
+            .resolve(
+                SoundCueKind::Impact,
+                SoundMaterial::Cloth,
+                SoundMaterial::Rock,
+            )
+            .unwrap();
+        assert_eq!(impact.kind, SoundCueKind::Impact);
+        assert_eq!(impact.materials, (SoundMaterial::Rock, SoundMaterial::None));
+        assert!(bank
+            .resolve(
+                SoundCueKind::Impact,
+                SoundMaterial::Cloth,
+                SoundMaterial::Squishy
+            )
+            .is_none());
+
+        let footstep = bank
+            .resolve(
+                SoundCueKind::Footstep,
+                SoundMaterial::None,
+                SoundMaterial::Rock,
+            )
+            .unwrap();
+        assert_eq!(footstep.kind, SoundCueKind::Footstep);
+    }
+}
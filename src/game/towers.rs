@@ -0,0 +1,275 @@
+use bevy::prelude::*;
+use bevy_gltf_blueprints::GltfBlueprintsSet;
+use serde::Deserialize;
+
+use crate::state::GameState;
+
+use super::{DamageKind, Dying, Enemy, Health, PathFollower, ProjectileMode};
+
+/// How a tower picks its target among the enemies in range
+#[derive(Reflect, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetingStrategy {
+    /// the enemy furthest along its path
+    #[default]
+    First,
+    /// the enemy the least far along its path
+    Last,
+    Nearest,
+    Strongest,
+    Weakest,
+}
+
+/// An enemy in range of a tower, with everything the targeting strategies need to compare them
+#[derive(Debug, Clone, Copy)]
+pub struct TargetCandidate {
+    pub entity: Entity,
+    pub distance: f32,
+    /// distance travelled along its path
+    pub progress: f32,
+    pub strength: f32,
+}
+
+impl TargetingStrategy {
+    pub fn pick(&self, candidates: &[TargetCandidate]) -> Option<Entity> {
+        let candidates = candidates.iter();
+        match self {
+            TargetingStrategy::First => candidates.max_by(|a, b| a.progress.total_cmp(&b.progress)),
+            TargetingStrategy::Last => candidates.min_by(|a, b| a.progress.total_cmp(&b.progress)),
+            TargetingStrategy::Nearest => {
+                candidates.min_by(|a, b| a.distance.total_cmp(&b.distance))
+            }
+            TargetingStrategy::Strongest => {
+                candidates.max_by(|a, b| a.strength.total_cmp(&b.strength))
+            }
+            TargetingStrategy::Weakest => {
+                candidates.min_by(|a, b| a.strength.total_cmp(&b.strength))
+            }
+        }
+        .map(|candidate| candidate.entity)
+    }
+}
+
+#[derive(Component, Reflect, Deserialize, Debug, Clone)]
+#[reflect(Component)]
+#[serde(default)]
+/// Add this component to a blueprint to turn it into a tower, placed towers get the one of their `TowerDefinition`
+pub struct Tower {
+    pub range: f32,
+    /// shots per second
+    pub fire_rate: f32,
+    pub damage: f32,
+    pub damage_kind: DamageKind,
+    /// name of the blueprint used for the projectiles
+    pub projectile: String,
+    pub projectile_mode: ProjectileMode,
+    /// in units per second
+    pub projectile_speed: f32,
+    /// 0.0 only damages whatever gets hit
+    pub splash_radius: f32,
+    pub targeting: TargetingStrategy,
+}
+impl Default for Tower {
+    fn default() -> Self {
+        Tower {
+            range: 8.0,
+            fire_rate: 1.0,
+            damage: 10.0,
+            damage_kind: DamageKind::Physical,
+            projectile: String::new(),
+            projectile_mode: ProjectileMode::Homing,
+            projectile_speed: 12.0,
+            splash_radius: 0.0,
+            targeting: TargetingStrategy::First,
+        }
+    }
+}
+
+impl Tower {
+    pub fn cooldown(&self) -> f32 {
+        1.0 / self.fire_rate.max(0.01)
+    }
+}
+
+#[derive(Component, Debug, Deref, DerefMut)]
+pub struct TowerCooldown(pub Timer);
+
+#[derive(Component, Debug, Default, Deref, DerefMut)]
+pub struct TowerTarget(pub Option<Entity>);
+
+#[derive(Event, Debug)]
+pub struct TowerFired {
+    pub tower: Entity,
+    pub target: Entity,
+}
+
+pub fn setup_towers(mut commands: Commands, added_towers: Query<(Entity, &Tower), Added<Tower>>) {
+    for (entity, tower) in added_towers.iter() {
+        // towers are ready to fire as soon as they are built
+        let mut cooldown = Timer::from_seconds(tower.cooldown(), TimerMode::Once);
+        cooldown.tick(cooldown.duration());
+        commands
+            .entity(entity)
+            .insert((TowerCooldown(cooldown), TowerTarget::default()));
+    }
+}
+
+pub fn tower_targeting(
+    mut towers: Query<(&Tower, &GlobalTransform, &mut TowerTarget)>,
+    enemies: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Option<&PathFollower>,
+            Option<&Health>,
+        ),
+        (With<Enemy>, Without<Dying>),
+    >,
+) {
+    for (tower, tower_transform, mut target) in towers.iter_mut() {
+        let candidates: Vec<TargetCandidate> = enemies
+            .iter()
+            .filter_map(|(entity, enemy_transform, follower, health)| {
+                let distance = tower_transform
+                    .translation()
+                    .distance(enemy_transform.translation());
+                (distance <= tower.range).then(|| TargetCandidate {
+                    entity,
+                    distance,
+                    progress: follower.map_or(0.0, |follower| follower.distance_travelled),
+                    strength: health.map_or(0.0, |health| health.0),
+                })
+            })
+            .collect();
+
+        target.0 = tower.targeting.pick(&candidates);
+    }
+}
+
+pub fn tower_firing(
+    time: Res<Time>,
+    mut towers: Query<(Entity, &Tower, &TowerTarget, &mut TowerCooldown)>,
+    mut tower_fired_events: EventWriter<TowerFired>,
+) {
+    for (entity, tower, target, mut cooldown) in towers.iter_mut() {
+        cooldown.tick(time.delta());
+        let Some(target) = target.0 else {
+            continue;
+        };
+        if cooldown.finished() {
+            tower_fired_events.send(TowerFired {
+                tower: entity,
+                target,
+            });
+            // fire rate might have changed since last shot
+            cooldown.0 = Timer::from_seconds(tower.cooldown(), TimerMode::Once);
+        }
+    }
+}
+
+pub struct TowersPlugin;
+impl Plugin for TowersPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Tower>()
+            .register_type::<TargetingStrategy>()
+            .add_event::<TowerFired>()
+            .add_systems(
+                Update,
+                (
+                    setup_towers.after(GltfBlueprintsSet::AfterSpawn),
+                    (tower_targeting, tower_firing)
+                        .chain()
+                        .run_if(in_state(GameState::InGame)),
+                ),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+    use crate::state::StatePlugin;
+
+    fn candidate(index: u32, distance: f32, progress: f32, strength: f32) -> TargetCandidate {
+        TargetCandidate {
+            entity: Entity::from_raw(index),
+            distance,
+            progress,
+            strength,
+        }
+    }
+
+    #[test]
+    fn each_strategy_picks_its_own_target() {
+        // the leader is the weakest, the nearest is the strongest
+        let candidates = [
+            candidate(0, 6.0, 12.0, 5.0),
+            candidate(1, 2.0, 4.0, 40.0),
+            candidate(2, 4.0, 1.0, 20.0),
+        ];
+        let pick = |strategy: TargetingStrategy| strategy.pick(&candidates).unwrap().index();
+        assert_eq!(pick(TargetingStrategy::First), 0);
+        assert_eq!(pick(TargetingStrategy::Last), 2);
+        assert_eq!(pick(TargetingStrategy::Nearest), 1);
+        assert_eq!(pick(TargetingStrategy::Strongest), 1);
+        assert_eq!(pick(TargetingStrategy::Weakest), 0);
+        assert_eq!(TargetingStrategy::First.pick(&[]), None);
+    }
+
+    #[test]
+    fn towers_fire_at_the_enemy_in_range_once_per_cooldown() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin, TowersPlugin))
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                0.25,
+            )));
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+        let tower = app
+            .world
+            .spawn((
+                Tower {
+                    range: 5.0,
+                    fire_rate: 1.0,
+                    targeting: TargetingStrategy::First,
+                    ..default()
+                },
+                GlobalTransform::default(),
+            ))
+            .id();
+        let enemy = |progress: f32, position: Vec3| {
+            (
+                Enemy,
+                PathFollower {
+                    distance_travelled: progress,
+                    ..default()
+                },
+                GlobalTransform::from_translation(position),
+            )
+        };
+        let in_range = app.world.spawn(enemy(1.0, Vec3::new(3.0, 0.0, 0.0))).id();
+        // further along, but out of range or dying
+        app.world.spawn(enemy(9.0, Vec3::new(8.0, 0.0, 0.0)));
+        app.world.spawn((
+            enemy(9.0, Vec3::new(1.0, 0.0, 0.0)),
+            Dying(Timer::from_seconds(1.0, TimerMode::Once)),
+        ));
+
+        let mut reader = app.world.resource::<Events<TowerFired>>().get_reader();
+        let mut shots = Vec::new();
+        // the first shot goes as soon as the tower is set up, then one every second
+        for _ in 0..10 {
+            app.update();
+            shots.extend(
+                reader
+                    .read(app.world.resource::<Events<TowerFired>>())
+                    .map(|fired| (fired.tower, fired.target)),
+            );
+        }
+        assert_eq!(shots, vec![(tower, in_range); 3]);
+    }
+}
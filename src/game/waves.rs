@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_gltf_blueprints::{BluePrintBundle, BlueprintName, GameWorldTag};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    assets::GameAssets,
+    core::{CollisionLayersProxy, GameLayer},
+    state::{AppState, GameState},
+};
+
+use super::{level_root, Health, MaxHealth, PathFollower, Persist};
+
+/// A single wave, as described in the `*.waves.ron` files
+#[derive(Deserialize, Debug, Clone)]
+pub struct WaveDefinition {
+    /// name of the blueprint to spawn (from `models/library`)
+    pub enemy: String,
+    /// how many enemies this wave contains
+    pub count: u32,
+    /// seconds between two enemies of this wave
+    pub interval: f32,
+    /// name of the entity in the level the enemies are spawned at
+    pub spawn_point: String,
+    /// seconds to wait before this wave starts
+    pub delay: f32,
+    /// name of the path (see `PathWaypoint`) the enemies walk along
+    pub path: String,
+    /// walking speed of the enemies, in units per second
+    pub speed: f32,
+    /// starting (& maximum) health of each enemy
+    pub health: f32,
+    /// gold earned when the wave is cleared
+    #[serde(default)]
+    pub reward: u32,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct WavesDefinition {
+    pub waves: Vec<WaveDefinition>,
+}
+
+#[derive(Component, Reflect, Default, Debug)]
+#[reflect(Component)]
+/// Marker component for enemies, inserted on everything spawned by the wave spawner
+pub struct Enemy;
+
+#[derive(Event, Debug)]
+pub struct WaveStarted {
+    pub index: usize,
+}
+
+#[derive(Event, Debug)]
+pub struct WaveCleared {
+    pub index: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WaveSpawnerStatus {
+    /// waiting for the delay of the current wave to run out
+    #[default]
+    Waiting,
+    /// spawning the enemies of the current wave
+    Spawning,
+    /// all enemies of the current wave are spawned, waiting for them to be gone
+    Fighting,
+    /// no waves left
+    Finished,
+}
+
+/// How far along its current wave a spawner is, as saved
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct WaveProgress {
+    pub status: WaveSpawnerStatus,
+    pub spawned: u32,
+    /// seconds elapsed on the delay or interval timer
+    pub elapsed: f32,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct WaveSpawner {
+    /// index of the current wave
+    pub current: usize,
+    pub status: WaveSpawnerStatus,
+    spawned: u32,
+    timer: Timer,
+}
+
+impl WaveSpawner {
+    /// a spawner waiting to start the wave at `index`, `wave` being its definition if there is such a wave
+    pub fn starting_at(index: usize, wave: Option<&WaveDefinition>) -> Self {
+        let mut spawner = WaveSpawner {
+            current: index,
+            ..default()
+        };
+        spawner.wait_for(wave);
+        spawner
+    }
+
+    /// a spawner picking up the wave at `index` where `progress` left it
+    pub fn resuming(index: usize, wave: Option<&WaveDefinition>, progress: &WaveProgress) -> Self {
+        let mut spawner = WaveSpawner::starting_at(index, wave);
+        let Some(wave) = wave else {
+            return spawner;
+        };
+        spawner.status = progress.status;
+        spawner.spawned = progress.spawned;
+        if progress.status == WaveSpawnerStatus::Spawning {
+            spawner.timer =
+                Timer::new(Duration::from_secs_f32(wave.interval), TimerMode::Repeating);
+        }
+        spawner
+            .timer
+            .set_elapsed(Duration::from_secs_f32(progress.elapsed));
+        spawner
+    }
+
+    pub fn progress(&self) -> WaveProgress {
+        WaveProgress {
+            status: self.status,
+            spawned: self.spawned,
+            elapsed: self.timer.elapsed_secs(),
+        }
+    }
+
+    fn wait_for(&mut self, wave: Option<&WaveDefinition>) {
+        match wave {
+            Some(wave) => {
+                self.status = WaveSpawnerStatus::Waiting;
+                self.timer = Timer::from_seconds(wave.delay, TimerMode::Once);
+            }
+            None => self.status = WaveSpawnerStatus::Finished,
+        }
+    }
+}
+
+pub fn reset_wave_spawner(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    waves_definitions: Res<Assets<WavesDefinition>>,
+) {
+    commands.insert_resource(WaveSpawner::starting_at(
+        0,
+        waves_definitions
+            .get(&game_assets.waves)
+            .and_then(|definition| definition.waves.first()),
+    ));
+}
+
+pub fn wave_spawner(
+    time: Res<Time>,
+    game_assets: Res<GameAssets>,
+    waves_definitions: Res<Assets<WavesDefinition>>,
+    mut spawner: ResMut<WaveSpawner>,
+    spawn_points: Query<(&Name, &GlobalTransform)>,
+    enemies: Query<(), With<Enemy>>,
+    game_world: Query<&Children, With<GameWorldTag>>,
+    mut wave_started_events: EventWriter<WaveStarted>,
+    mut wave_cleared_events: EventWriter<WaveCleared>,
+    mut commands: Commands,
+) {
+    let Some(definition) = waves_definitions.get(&game_assets.waves) else {
+        return;
+    };
+    let index = spawner.current;
+    let Some(wave) = definition.waves.get(index) else {
+        spawner.status = WaveSpawnerStatus::Finished;
+        return;
+    };
+
+    match spawner.status {
+        WaveSpawnerStatus::Waiting => {
+            if spawner.timer.tick(time.delta()).finished() {
+                info!("starting wave {}", index);
+                spawner.status = WaveSpawnerStatus::Spawning;
+                spawner.spawned = 0;
+                spawner.timer =
+                    Timer::new(Duration::from_secs_f32(wave.interval), TimerMode::Repeating);
+                wave_started_events.send(WaveStarted { index });
+            }
+        }
+        WaveSpawnerStatus::Spawning => {
+            spawner.timer.tick(time.delta());
+            // the first enemy of a wave comes right away
+            if spawner.spawned > 0 && !spawner.timer.just_finished() {
+                return;
+            }
+            let Some(level_root) = level_root(&game_world) else {
+                warn!("no level to spawn the enemies of wave {} in", index);
+                return;
+            };
+
+            let transform = match spawn_points
+                .iter()
+                .find(|(name, _)| name.as_str() == wave.spawn_point)
+            {
+                Some((_, spawn_point)) => spawn_point.compute_transform(),
+                None => {
+                    // the blueprint validation (--validate-blueprints) catches this before shipping
+                    error!(
+                        "no spawn point called {} in the level, spawning at the origin",
+                        wave.spawn_point
+                    );
+                    Transform::default()
+                }
+            };
+
+            let enemy = commands
+                .spawn((
+                    BluePrintBundle {
+                        blueprint: BlueprintName(wave.enemy.clone()),
+                        transform: TransformBundle::from_transform(transform),
+                        ..Default::default()
+                    },
+                    Name::from(format!("{}_{}_{}", wave.enemy, index, spawner.spawned)),
+                    Enemy,
+                    PathFollower::new(wave.path.clone(), wave.speed),
+                    Health(wave.health),
+                    MaxHealth(wave.health),
+                    CollisionLayersProxy(vec![GameLayer::Enemy]),
+                    Persist,
+                ))
+                .id();
+            commands.entity(level_root).add_child(enemy);
+
+            spawner.spawned += 1;
+            if spawner.spawned >= wave.count {
+                spawner.status = WaveSpawnerStatus::Fighting;
+            }
+        }
+        WaveSpawnerStatus::Fighting => {
+            if enemies.is_empty() {
+                info!("wave {} cleared", index);
+                wave_cleared_events.send(WaveCleared { index });
+                spawner.current += 1;
+                spawner.wait_for(definition.waves.get(spawner.current));
+            }
+        }
+        WaveSpawnerStatus::Finished => {}
+    }
+}
+
+pub struct WavesPlugin;
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Enemy>()
+            .add_event::<WaveStarted>()
+            .add_event::<WaveCleared>()
+            .init_resource::<WaveSpawner>()
+            .add_systems(OnEnter(AppState::AppRunning), reset_wave_spawner)
+            .add_systems(Update, wave_spawner.run_if(in_state(GameState::InGame)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{DamageEvent, DamageKind, HealthPlugin};
+    use crate::state::StatePlugin;
+
+    #[test]
+    fn wave_spawned_enemies_can_be_killed() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            StatePlugin,
+            HealthPlugin,
+            WavesPlugin,
+        ))
+        .init_asset::<AnimationClip>()
+        .init_asset::<WavesDefinition>();
+
+        let wave: WaveDefinition = ron::from_str(
+            r#"(enemy: "Fox", count: 1, interval: 1.0, spawn_point: "EnemySpawn",
+                path: "EnemyPath", delay: 0.0, speed: 1.0, health: 25.0)"#,
+        )
+        .unwrap();
+        let waves = app
+            .world
+            .resource_mut::<Assets<WavesDefinition>>()
+            .add(WavesDefinition {
+                waves: vec![wave.clone()],
+            });
+        app.insert_resource(GameAssets {
+            world: Handle::default(),
+            models: default(),
+            waves,
+            towers: Handle::default(),
+            materials: Handle::default(),
+            sounds: Handle::default(),
+        })
+        .insert_resource(WaveSpawner::starting_at(0, Some(&wave)));
+        app.world.spawn(GameWorldTag).with_children(|world| {
+            world.spawn((Name::new("EnemySpawn"), TransformBundle::default()));
+        });
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(GameState::InGame);
+
+        // the wave starts, then spawns its single enemy
+        app.update();
+        app.update();
+        let enemy = app
+            .world
+            .query_filtered::<Entity, With<Enemy>>()
+            .single(&app.world);
+        assert_eq!(app.world.get::<Health>(enemy).unwrap().0, 25.0);
+        assert_eq!(app.world.get::<MaxHealth>(enemy).unwrap().0, 25.0);
+
+        for _ in 0..3 {
+            app.world.send_event(DamageEvent {
+                target: enemy,
+                amount: 10.0,
+                kind: DamageKind::Physical,
+                source: None,
+            });
+            app.update();
+        }
+        assert!(app.world.get_entity(enemy).is_none());
+
+        // no enemies left: the wave is cleared, & it was the last one
+        app.update();
+        let spawner = app.world.resource::<WaveSpawner>();
+        assert_eq!(spawner.current, 1);
+        assert_eq!(spawner.status, WaveSpawnerStatus::Finished);
+    }
+}
@@ -16,7 +16,20 @@ use game::*;
 mod test_components;
 use test_components::*;
 
+mod tools;
+
 fn main() {
+    // checks the blueprints library & levels without opening a window, for use in CI
+    if std::env::args().any(|arg| arg == "--validate-blueprints") {
+        std::process::exit(tools::validate_blueprints());
+    }
+    // writes the reflected types as a json schema, for the Blender side tooling
+    let mut args = std::env::args().skip_while(|arg| arg != "--export-registry");
+    if args.next().is_some() {
+        let path = args.next().unwrap_or_else(|| "registry.json".to_string());
+        std::process::exit(tools::export_registry(std::path::Path::new(&path)));
+    }
+
     App::new()
         .add_plugins((
             DefaultPlugins
@@ -48,5 +61,8 @@ fn main() {
             GamePlugin,           // specific to our game
             ComponentsTestPlugin, // Showcases different type of components /structs
         ))
+        .insert_resource(MainMenuSettings {
+            skip_to_game: std::env::args().any(|arg| arg == "--skip-menu"),
+        })
         .run();
 }
@@ -1,4 +1,5 @@
 use bevy::app::AppExit;
+use bevy::ecs::schedule::{apply_state_transition, StateTransition};
 use bevy::prelude::*;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default, States)]
@@ -8,7 +9,11 @@ pub enum AppState {
     MenuRunning,
     AppLoading,
     AppRunning,
+    /// passed through when restarting the level, so it gets set up anew without being loaded again
+    AppRestarting,
     AppEnding,
+    /// an asset could not be loaded, shows what went wrong
+    LoadingFailed,
 
     // FIXME: not sure
     LoadingGame,
@@ -21,6 +26,8 @@ pub enum GameState {
 
     InMenu,
     InGame,
+    /// gameplay, physics & animations are frozen, the pause menu is shown
+    Paused,
 
     InGameOver,
 
@@ -28,27 +35,109 @@ pub enum GameState {
     InLoading,
 }
 
-// tag components for all entities within a certain state (for despawning them if needed) , FIXME: seems kinda hack-ish
-#[derive(Component)]
-pub struct InCoreLoading;
-#[derive(Component, Default)]
-pub struct InMenuRunning;
-#[derive(Component)]
-pub struct InAppLoading;
-#[derive(Component)]
-pub struct InAppRunning;
-
-// components for tagging in game vs in game menu stuff
-#[derive(Component, Default)]
-pub struct InMainMenu;
-#[derive(Component, Default)]
-pub struct InMenu;
-#[derive(Component, Default)]
-pub struct InGame;
+#[derive(Component, Debug, Clone)]
+/// Entities with this component get despawned (recursively) when exiting the given state
+pub struct StateScoped<S: States>(pub S);
+
+// runs right after the state transitions, so the entities spawned by OnEnter of the new state are kept
+pub fn despawn_state_scoped<S: States>(
+    state: Res<State<S>>,
+    mut previous: Local<Option<S>>,
+    scoped: Query<(Entity, &StateScoped<S>)>,
+    mut commands: Commands,
+) {
+    let current = state.get().clone();
+    let Some(exited) = previous.replace(current.clone()) else {
+        return;
+    };
+    if exited == current {
+        return;
+    }
+    for (entity, scope) in scoped.iter() {
+        if scope.0 == exited {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub trait StateScopedExt {
+    /// despawns the entities with a `StateScoped<S>` when exiting their state
+    fn add_state_scoped<S: States>(&mut self) -> &mut Self;
+}
+
+impl StateScopedExt for App {
+    fn add_state_scoped<S: States>(&mut self) -> &mut Self {
+        self.add_systems(
+            StateTransition,
+            despawn_state_scoped::<S>.after(apply_state_transition::<S>),
+        )
+    }
+}
 
 pub struct StatePlugin;
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_state::<AppState>().add_state::<GameState>();
+        app.add_state::<AppState>()
+            .add_state::<GameState>()
+            .add_state_scoped::<AppState>()
+            .add_state_scoped::<GameState>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Component)]
+    struct SpawnedOnEnter;
+
+    fn spawn_on_enter(mut commands: Commands) {
+        commands.spawn((SpawnedOnEnter, StateScoped(AppState::AppRunning)));
+    }
+
+    fn set_states(app: &mut App, app_state: AppState, game_state: GameState) {
+        app.world
+            .resource_mut::<NextState<AppState>>()
+            .set(app_state);
+        app.world
+            .resource_mut::<NextState<GameState>>()
+            .set(game_state);
+        app.update();
+    }
+
+    #[test]
+    fn scoped_entities_go_away_with_their_state() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatePlugin))
+            .add_systems(OnEnter(AppState::AppRunning), spawn_on_enter);
+        set_states(&mut app, AppState::MenuRunning, GameState::InMenu);
+
+        let menu = app
+            .world
+            .spawn(StateScoped(AppState::MenuRunning))
+            .with_children(|menu| {
+                menu.spawn_empty();
+            })
+            .id();
+        let menu_child = app.world.get::<Children>(menu).unwrap()[0];
+        let menu_game_state = app.world.spawn(StateScoped(GameState::InMenu)).id();
+
+        set_states(&mut app, AppState::AppRunning, GameState::InGame);
+        assert!(app.world.get_entity(menu).is_none());
+        assert!(app.world.get_entity(menu_child).is_none());
+        assert!(app.world.get_entity(menu_game_state).is_none());
+        // what the new state spawns when entering it is kept
+        let level = app
+            .world
+            .query_filtered::<Entity, With<SpawnedOnEnter>>()
+            .single(&app.world);
+
+        let in_game = app.world.spawn(StateScoped(GameState::InGame)).id();
+        set_states(&mut app, AppState::AppRunning, GameState::Paused);
+        assert!(app.world.get_entity(in_game).is_none());
+        assert!(app.world.get_entity(level).is_some());
+
+        set_states(&mut app, AppState::MenuRunning, GameState::None);
+        assert!(app.world.get_entity(level).is_none());
     }
 }
@@ -1,47 +1,47 @@
 use bevy::prelude::*;
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct UnitTest;
 
 #[derive(Component, Reflect, Default, Debug, Deref, DerefMut)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleTestF32(f32);
 
 #[derive(Component, Reflect, Default, Debug, Deref, DerefMut)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleTestU64(u64);
 
 #[derive(Component, Reflect, Default, Debug, Deref, DerefMut)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub struct TuppleTestStr(String);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleTest2(f32, u64, String);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleTestBool(bool);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleVec2(Vec2);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleVec3(Vec3);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleVec(Vec<String>);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct TuppleTestColor(Color);
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 struct BasicTest {
     a: f32,
     b: u64,
@@ -49,7 +49,7 @@ struct BasicTest {
 }
 
 #[derive(Component, Reflect, Default, Debug)]
-#[reflect(Component)]
+#[reflect(Component, Default)]
 pub enum EnumTest {
     Metal,
     Wood,
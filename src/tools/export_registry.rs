@@ -0,0 +1,371 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy::reflect::serde::TypedReflectSerializer;
+use bevy::reflect::{
+    NamedField, TypeInfo, TypeRegistration, TypeRegistry, UnnamedField, VariantInfo,
+};
+use serde_json::{json, Map, Value};
+
+use super::headless_app;
+
+/// Writes a json schema of every reflected type of the game to `path`, for the Blender side tooling.
+/// Returns the process exit code
+pub fn export_registry(path: &Path) -> i32 {
+    let app = headless_app();
+    let type_registry = app.world.resource::<AppTypeRegistry>().read();
+    let schema = registry_schema(&type_registry);
+    let types = schema["$defs"].as_object().map_or(0, Map::len);
+    let components = type_registry
+        .iter()
+        .filter(|registration| registration.data::<ReflectComponent>().is_some())
+        .count();
+
+    let result = serde_json::to_string_pretty(&schema)
+        .map_err(|error| error.to_string())
+        .and_then(|content| fs::write(path, content).map_err(|error| error.to_string()));
+    match result {
+        Ok(()) => {
+            println!(
+                "exported {} types ({} components) to {}",
+                types,
+                components,
+                path.display()
+            );
+            0
+        }
+        Err(error) => {
+            eprintln!(
+                "could not export the registry to {}: {}",
+                path.display(),
+                error
+            );
+            1
+        }
+    }
+}
+
+/// The json schema of every type in the registry, under `$defs` by type path.
+/// What json schema has no keyword for is prefixed with `x-`: components (the types that can be used as
+/// gltf custom properties) are flagged with `x-isComponent`, resources with `x-isResource`, the kind of
+/// reflected type is in `x-typeInfo` & the key type of maps in `x-keyType`.
+/// Types with a reflected `Default` get their default value, in json like the values it describes
+pub fn registry_schema(type_registry: &TypeRegistry) -> Value {
+    let definitions: Map<String, Value> = type_registry
+        .iter()
+        .map(|registration| {
+            (
+                registration.type_info().type_path().to_string(),
+                type_schema(registration, type_registry),
+            )
+        })
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "td3 reflected types",
+        "$defs": definitions,
+    })
+}
+
+fn type_ref(type_path: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", type_path) })
+}
+
+fn named_fields<'a>(
+    fields: impl Iterator<Item = &'a NamedField>,
+) -> (Map<String, Value>, Vec<Value>) {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in fields {
+        properties.insert(field.name().to_string(), type_ref(field.type_path()));
+        required.push(Value::from(field.name()));
+    }
+    (properties, required)
+}
+
+fn unnamed_fields<'a>(fields: impl Iterator<Item = &'a UnnamedField>) -> Vec<Value> {
+    fields.map(|field| type_ref(field.type_path())).collect()
+}
+
+// json type of the reflected "value" types (primitives, strings...), whatever is not described by its fields
+fn value_type(type_path: &str) -> &'static str {
+    match type_path {
+        "bool" => "boolean",
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+        | "isize" => "integer",
+        "f32" | "f64" => "number",
+        "char" | "alloc::string::String" | "str" | "&str" | "alloc::borrow::Cow<str>" => "string",
+        _ => "object",
+    }
+}
+
+// glam types serialize as arrays of numbers, not as the structs they are reflected as
+fn math_schema(type_path: &str) -> Option<Value> {
+    let (length, item_type) = match type_path {
+        "glam::Vec2" => (2, "number"),
+        "glam::Vec3" | "glam::Vec3A" => (3, "number"),
+        "glam::Vec4" | "glam::Quat" => (4, "number"),
+        "glam::IVec2" | "glam::UVec2" => (2, "integer"),
+        "glam::IVec3" | "glam::UVec3" => (3, "integer"),
+        "glam::IVec4" | "glam::UVec4" => (4, "integer"),
+        _ => return None,
+    };
+    Some(json!({
+        "type": "array",
+        "items": { "type": item_type },
+        "minItems": length,
+        "maxItems": length,
+    }))
+}
+
+// colors serialize as one of their color spaces, each with its channels as numbers
+fn color_schema() -> Value {
+    let color_space = |name: &str, channels: [&str; 4]| {
+        let properties: Map<String, Value> = channels
+            .iter()
+            .map(|channel| (channel.to_string(), json!({ "type": "number" })))
+            .collect();
+        json!({
+            "title": name,
+            "type": "object",
+            "properties": {
+                (name): { "type": "object", "properties": properties, "required": channels },
+            },
+            "required": [name],
+        })
+    };
+    json!({
+        "oneOf": [
+            color_space("Rgba", ["red", "green", "blue", "alpha"]),
+            color_space("RgbaLinear", ["red", "green", "blue", "alpha"]),
+            color_space("Hsla", ["hue", "saturation", "lightness", "alpha"]),
+            color_space("Lcha", ["lightness", "chroma", "hue", "alpha"]),
+        ],
+    })
+}
+
+fn variant_schema(variant: &VariantInfo) -> Value {
+    match variant {
+        VariantInfo::Unit(variant) => json!({
+            "title": variant.name(),
+            "const": variant.name(),
+        }),
+        VariantInfo::Tuple(variant) => json!({
+            "title": variant.name(),
+            "type": "object",
+            "properties": {
+                (variant.name()): {
+                    "type": "array",
+                    "prefixItems": unnamed_fields(variant.iter()),
+                },
+            },
+            "required": [variant.name()],
+        }),
+        VariantInfo::Struct(variant) => {
+            let (properties, required) = named_fields(variant.iter());
+            json!({
+                "title": variant.name(),
+                "type": "object",
+                "properties": {
+                    (variant.name()): {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    },
+                },
+                "required": [variant.name()],
+            })
+        }
+    }
+}
+
+fn type_schema(registration: &TypeRegistration, type_registry: &TypeRegistry) -> Value {
+    let type_info = registration.type_info();
+    let mut schema = Map::new();
+    schema.insert(
+        "title".to_string(),
+        Value::from(type_info.type_path_table().short_path()),
+    );
+    schema.insert(
+        "x-isComponent".to_string(),
+        Value::from(registration.data::<ReflectComponent>().is_some()),
+    );
+    schema.insert(
+        "x-isResource".to_string(),
+        Value::from(registration.data::<ReflectResource>().is_some()),
+    );
+
+    let (type_info_kind, details) = match type_info {
+        TypeInfo::Struct(info) => {
+            let (properties, required) = named_fields(info.iter());
+            (
+                "Struct",
+                json!({ "type": "object", "properties": properties, "required": required }),
+            )
+        }
+        TypeInfo::TupleStruct(info) => (
+            "TupleStruct",
+            json!({ "type": "array", "prefixItems": unnamed_fields(info.iter()) }),
+        ),
+        TypeInfo::Tuple(info) => (
+            "Tuple",
+            json!({ "type": "array", "prefixItems": unnamed_fields(info.iter()) }),
+        ),
+        TypeInfo::List(info) => (
+            "List",
+            json!({ "type": "array", "items": type_ref(info.item_type_path_table().path()) }),
+        ),
+        TypeInfo::Array(info) => (
+            "Array",
+            json!({
+                "type": "array",
+                "items": type_ref(info.item_type_path_table().path()),
+                "minItems": info.capacity(),
+                "maxItems": info.capacity(),
+            }),
+        ),
+        TypeInfo::Map(info) => (
+            "Map",
+            json!({
+                "type": "object",
+                "x-keyType": type_ref(info.key_type_path_table().path()),
+                "additionalProperties": type_ref(info.value_type_path_table().path()),
+            }),
+        ),
+        TypeInfo::Enum(info) => (
+            "Enum",
+            json!({ "oneOf": info.iter().map(variant_schema).collect::<Vec<_>>() }),
+        ),
+        TypeInfo::Value(info) => ("Value", json!({ "type": value_type(info.type_path()) })),
+    };
+    schema.insert("x-typeInfo".to_string(), Value::from(type_info_kind));
+    // some types serialize differently from how they are reflected
+    let details = match type_info.type_path() {
+        "bevy_render::color::Color" => color_schema(),
+        type_path => math_schema(type_path).unwrap_or(details),
+    };
+    if let Value::Object(details) = details {
+        schema.extend(details);
+    }
+
+    if let Some(default) = registration
+        .data::<ReflectDefault>()
+        .map(|reflect_default| reflect_default.default())
+        .and_then(|default| {
+            serde_json::to_value(TypedReflectSerializer::new(&*default, type_registry)).ok()
+        })
+    {
+        schema.insert("default".to_string(), default);
+    }
+    Value::Object(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Default)]
+    struct Turret {
+        range: f32,
+        offset: Vec3,
+        tint: Color,
+    }
+
+    #[derive(Reflect)]
+    enum Targeting {
+        First,
+        Within(f32),
+    }
+
+    #[derive(Component, Reflect, Default)]
+    #[reflect(Component, Default)]
+    struct Lives(u32);
+
+    fn schema() -> Value {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Vec3>();
+        type_registry.register::<Color>();
+        type_registry.register::<Turret>();
+        type_registry.register::<Targeting>();
+        type_registry.register::<Lives>();
+        registry_schema(&type_registry)
+    }
+
+    #[test]
+    fn structs_are_objects_with_a_json_default() {
+        let schema = schema();
+        let turret = &schema["$defs"][Turret::type_path()];
+        assert_eq!(turret["type"], "object");
+        assert_eq!(turret["x-typeInfo"], "Struct");
+        assert_eq!(turret["x-isComponent"], false);
+        assert_eq!(turret["properties"]["range"], type_ref("f32"));
+        assert_eq!(turret["required"], json!(["range", "offset", "tint"]));
+        assert_eq!(turret["default"]["range"], json!(0.0));
+        assert_eq!(turret["default"]["offset"], json!([0.0, 0.0, 0.0]));
+        assert!(turret["default"]["tint"]["Rgba"].is_object());
+
+        // the fields refer to the way their own types are serialized
+        let vec3 = &schema["$defs"]["glam::Vec3"];
+        assert_eq!(vec3["type"], "array");
+        assert_eq!(vec3["items"]["type"], "number");
+        assert_eq!(vec3["minItems"], 3);
+        let color = &schema["$defs"]["bevy_render::color::Color"];
+        assert_eq!(color["oneOf"][0]["title"], "Rgba");
+    }
+
+    #[test]
+    fn enums_are_one_of_their_variants() {
+        let schema = schema();
+        let targeting = &schema["$defs"][Targeting::type_path()];
+        assert_eq!(targeting["x-typeInfo"], "Enum");
+        assert_eq!(
+            targeting["oneOf"][0],
+            json!({ "title": "First", "const": "First" })
+        );
+        assert_eq!(
+            targeting["oneOf"][1]["properties"]["Within"]["prefixItems"],
+            json!([type_ref("f32")])
+        );
+        assert!(targeting.get("default").is_none());
+    }
+
+    #[test]
+    fn tuple_structs_are_arrays() {
+        let schema = schema();
+        let lives = &schema["$defs"][Lives::type_path()];
+        assert_eq!(lives["type"], "array");
+        assert_eq!(lives["x-typeInfo"], "TupleStruct");
+        assert_eq!(lives["x-isComponent"], true);
+        assert_eq!(lives["prefixItems"], json!([type_ref("u32")]));
+        assert_eq!(lives["default"], json!([0]));
+    }
+
+    #[test]
+    fn metadata_is_prefixed() {
+        let keywords = [
+            "title",
+            "type",
+            "properties",
+            "required",
+            "prefixItems",
+            "items",
+            "minItems",
+            "maxItems",
+            "additionalProperties",
+            "oneOf",
+            "default",
+        ];
+        for (type_path, definition) in schema()["$defs"].as_object().unwrap() {
+            for key in definition.as_object().unwrap().keys() {
+                assert!(
+                    keywords.contains(&key.as_str()) || key.starts_with("x-"),
+                    "{} has a custom {} keyword",
+                    type_path,
+                    key
+                );
+            }
+        }
+    }
+}
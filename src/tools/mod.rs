@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use bevy::render::{settings::WgpuSettings, RenderPlugin};
+use bevy::winit::WinitPlugin;
+
+use crate::test_components::ComponentsTestPlugin;
+use crate::{assets::AssetsPlugin, core::CorePlugin, game::GamePlugin, state::StatePlugin};
+
+pub mod export_registry;
+pub use export_registry::*;
+
+pub mod validate_blueprints;
+pub use validate_blueprints::*;
+
+/// The game's plugins without a window, renderer or editor, for command line tools that only need
+/// what the plugins register (reflected types, assets...). The app is built but never run
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: bevy::window::ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: WgpuSettings {
+                    backends: None,
+                    ..default()
+                }
+                .into(),
+            })
+            .disable::<WinitPlugin>(),
+        StatePlugin,
+        AssetsPlugin,
+        CorePlugin,
+        GamePlugin,
+        ComponentsTestPlugin,
+    ));
+    app
+}
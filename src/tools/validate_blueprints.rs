@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::prelude::*;
+use bevy::reflect::serde::UntypedReflectDeserializer;
+use bevy::reflect::{TypeInfo, TypeRegistry};
+use serde::de::DeserializeSeed;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::core::BLUEPRINTS_LIBRARY_FOLDER;
+use crate::game::{LevelCatalog, TowerCatalog, WavesDefinition, HEALTH_PICKUP_BLUEPRINT};
+
+use super::headless_app;
+
+// blueprints spawned by name from code, rather than from a level or definition file
+const CODE_BLUEPRINTS: [&str; 1] = [HEALTH_PICKUP_BLUEPRINT];
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: &[u8; 4] = b"JSON";
+
+#[derive(Default, Debug)]
+struct ValidationReport {
+    library_files: usize,
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Checks the blueprints library & the levels against each other & against the reflected types of the game:
+/// unknown blueprint names, unregistered or malformed components & unused library files.
+/// Prints a report & returns the process exit code, non zero if there were errors
+pub fn validate_blueprints() -> i32 {
+    let app = headless_app();
+    let type_registry = app.world.resource::<AppTypeRegistry>().read();
+    let assets_folder = FileAssetReader::get_base_path().join("assets");
+    let report = validate(&assets_folder, &type_registry);
+
+    for warning in report.warnings.iter() {
+        println!("warning: {}", warning);
+    }
+    for error in report.errors.iter() {
+        println!("error: {}", error);
+    }
+    println!(
+        "checked {} library files: {} errors, {} warnings",
+        report.library_files,
+        report.errors.len(),
+        report.warnings.len()
+    );
+
+    if report.errors.is_empty() {
+        0
+    } else {
+        1
+    }
+}
+
+fn validate(assets_folder: &Path, type_registry: &TypeRegistry) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    // blueprint name => where it is referenced
+    let mut references: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    let library_folder = assets_folder.join(BLUEPRINTS_LIBRARY_FOLDER);
+    let library = gltf_files(&library_folder, &mut report);
+    report.library_files = library.len();
+    for path in library.values() {
+        check_gltf(path, type_registry, &mut references, &mut report);
+    }
+
+    for catalog_path in
+        files_with_extension(&assets_folder.join("levels"), "levels.ron", &mut report)
+    {
+        let Some(catalog) = read_ron::<LevelCatalog>(&catalog_path, &mut report) else {
+            continue;
+        };
+        for level in catalog.levels {
+            check_gltf(
+                &assets_folder.join(&level.world),
+                type_registry,
+                &mut references,
+                &mut report,
+            );
+            let waves_path = assets_folder.join(&level.waves);
+            if let Some(waves) = read_ron::<WavesDefinition>(&waves_path, &mut report) {
+                check_level_nodes(
+                    &assets_folder.join(&level.world),
+                    &waves,
+                    &waves_path,
+                    &mut report,
+                );
+                for wave in waves.waves {
+                    add_reference(&mut references, &wave.enemy, &waves_path);
+                }
+            }
+        }
+    }
+
+    for towers_path in files_with_extension(
+        &assets_folder.join("definitions"),
+        "towers.ron",
+        &mut report,
+    ) {
+        if let Some(towers) = read_ron::<TowerCatalog>(&towers_path, &mut report) {
+            for tower in towers.towers {
+                add_reference(&mut references, &tower.blueprint, &towers_path);
+            }
+        }
+    }
+
+    for name in CODE_BLUEPRINTS {
+        references
+            .entry(name.to_string())
+            .or_default()
+            .push("code".to_string());
+    }
+
+    for (name, places) in references.iter() {
+        if !library.contains_key(name) {
+            report.errors.push(format!(
+                "unknown blueprint {}, referenced in {}",
+                name,
+                places.join(", ")
+            ));
+        }
+    }
+    for (name, path) in library.iter() {
+        if !references.contains_key(name) {
+            report
+                .warnings
+                .push(format!("unused library file {}", path.display()));
+        }
+    }
+
+    report
+}
+
+fn add_reference(references: &mut BTreeMap<String, Vec<String>>, name: &str, path: &Path) {
+    let places = references.entry(name.to_string()).or_default();
+    let place = path.display().to_string();
+    if !places.contains(&place) {
+        places.push(place);
+    }
+}
+
+fn files_with_extension(
+    folder: &Path,
+    extension: &str,
+    report: &mut ValidationReport,
+) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(error) => {
+            report
+                .errors
+                .push(format!("could not read {}: {}", folder.display(), error));
+            return Vec::new();
+        }
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(&format!(".{}", extension)))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+// blueprint name (the file stem) => path, like the BlueprintsPlugin looks them up
+fn gltf_files(folder: &Path, report: &mut ValidationReport) -> BTreeMap<String, PathBuf> {
+    let mut files = files_with_extension(folder, "glb", report);
+    files.extend(files_with_extension(folder, "gltf", report));
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect()
+}
+
+fn read_ron<T: for<'de> serde::Deserialize<'de>>(
+    path: &Path,
+    report: &mut ValidationReport,
+) -> Option<T> {
+    let result = fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|content| ron::from_str(&content).map_err(|error| error.to_string()));
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            report
+                .errors
+                .push(format!("could not read {}: {}", path.display(), error));
+            None
+        }
+    }
+}
+
+// the json part of a .gltf file, or the first chunk of a binary .glb file
+fn read_gltf_json(path: &Path) -> Result<Value, String> {
+    let bytes = fs::read(path).map_err(|error| error.to_string())?;
+    let json = if bytes.starts_with(GLB_MAGIC) {
+        if bytes.len() < 20 || &bytes[16..20] != GLB_JSON_CHUNK {
+            return Err("no json chunk".to_string());
+        }
+        let length = u32::from_le_bytes([bytes[12], bytes[13], bytes[14], bytes[15]]) as usize;
+        bytes
+            .get(20..20 + length)
+            .ok_or_else(|| "truncated json chunk".to_string())?
+    } else {
+        &bytes[..]
+    };
+    serde_json::from_slice(json).map_err(|error| error.to_string())
+}
+
+fn check_gltf(
+    path: &Path,
+    type_registry: &TypeRegistry,
+    references: &mut BTreeMap<String, Vec<String>>,
+    report: &mut ValidationReport,
+) {
+    let json = match read_gltf_json(path) {
+        Ok(json) => json,
+        Err(error) => {
+            report
+                .errors
+                .push(format!("could not read {}: {}", path.display(), error));
+            return;
+        }
+    };
+    let Some(nodes) = json.get("nodes").and_then(Value::as_array) else {
+        return;
+    };
+    for node in nodes {
+        let Some(extras) = node.get("extras").and_then(Value::as_object) else {
+            continue;
+        };
+        let node_name = node
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or("<unnamed>");
+        for (key, value) in extras {
+            if let Err(error) = check_component(type_registry, key, value) {
+                report.errors.push(format!(
+                    "{} (node {}): {}",
+                    path.display(),
+                    node_name,
+                    error
+                ));
+            }
+            if key == "BlueprintName" {
+                let raw = value.as_str().unwrap_or_default().trim();
+                let name = ron::from_str::<String>(raw).unwrap_or_else(|_| raw.to_string());
+                add_reference(references, &name, path);
+            }
+        }
+    }
+}
+
+// only the part of a `PathWaypoint` custom property needed to know which paths a level has
+#[derive(Deserialize)]
+struct WaypointPath {
+    path: String,
+}
+
+// the spawn points & paths the waves of a level walk along have to be authored in the level itself
+fn check_level_nodes(
+    world_path: &Path,
+    waves: &WavesDefinition,
+    waves_path: &Path,
+    report: &mut ValidationReport,
+) {
+    // an unreadable level is already reported by check_gltf
+    let Ok(json) = read_gltf_json(world_path) else {
+        return;
+    };
+    let nodes = json
+        .get("nodes")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let names: BTreeSet<&str> = nodes
+        .iter()
+        .filter_map(|node| node.get("name").and_then(Value::as_str))
+        .collect();
+    let paths: BTreeSet<String> = nodes
+        .iter()
+        .filter_map(|node| node.get("extras")?.get("PathWaypoint")?.as_str())
+        .filter_map(|waypoint| ron::from_str::<WaypointPath>(waypoint).ok())
+        .map(|waypoint| waypoint.path)
+        .collect();
+
+    for wave in waves.waves.iter() {
+        if !names.contains(wave.spawn_point.as_str()) {
+            report.errors.push(format!(
+                "no spawn point called {} in {}, referenced in {}",
+                wave.spawn_point,
+                world_path.display(),
+                waves_path.display()
+            ));
+        }
+        if !paths.contains(&wave.path) {
+            report.errors.push(format!(
+                "no waypoints for the path {} in {}, referenced in {}",
+                wave.path,
+                world_path.display(),
+                waves_path.display()
+            ));
+        }
+    }
+}
+
+// mirrors how bevy_gltf_components turns gltf extras into reflected components
+fn check_component(type_registry: &TypeRegistry, key: &str, value: &Value) -> Result<(), String> {
+    let type_name = capitalize_first_letter(key.replace("component: ", "").trim());
+    let Some(registration) = type_registry.get_with_short_type_path(&type_name) else {
+        // bevy_gltf_components cannot pick between types sharing a short name either
+        let candidates: Vec<&str> = type_registry
+            .iter()
+            .map(|registration| registration.type_info().type_path_table())
+            .filter(|table| table.short_path() == type_name)
+            .map(|table| table.path())
+            .collect();
+        if candidates.len() > 1 {
+            return Err(format!(
+                "ambiguous component type {}: {}",
+                type_name,
+                candidates.join(", ")
+            ));
+        }
+        return Err(format!("unregistered component type {}", type_name));
+    };
+    if registration.data::<ReflectComponent>().is_none() {
+        return Err(format!(
+            "{} is registered but not reflected as a component",
+            type_name
+        ));
+    }
+
+    let mut parsed_value = match value {
+        Value::String(value) => value.trim().to_string(),
+        other => ron::to_string(other).map_err(|error| error.to_string())?,
+    };
+    if parsed_value.is_empty() {
+        parsed_value = "()".to_string();
+    } else if let TypeInfo::TupleStruct(info) = registration.type_info() {
+        if info.field_len() == 1 {
+            parsed_value = format!("({})", parsed_value);
+        }
+    }
+
+    let type_path = registration.type_info().type_path();
+    let ron_string = format!("{{ \"{}\":{} }}", type_path, parsed_value);
+    let mut deserializer = ron::Deserializer::from_str(&ron_string).map_err(|error| {
+        format!(
+            "malformed value {} for {}: {}",
+            parsed_value, type_name, error
+        )
+    })?;
+    UntypedReflectDeserializer::new(type_registry)
+        .deserialize(&mut deserializer)
+        .map_err(|error| {
+            format!(
+                "malformed value {} for {}: {}",
+                parsed_value, type_name, error
+            )
+        })?;
+    Ok(())
+}
+
+fn capitalize_first_letter(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_assets_are_valid() {
+        let app = headless_app();
+        let type_registry = app.world.resource::<AppTypeRegistry>().read();
+        let assets_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let report = validate(&assets_folder, &type_registry);
+        assert!(report.library_files > 0);
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn shipped_levels_have_the_spawn_points_and_paths_of_their_waves() {
+        let assets_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let mut report = ValidationReport::default();
+        let catalogs =
+            files_with_extension(&assets_folder.join("levels"), "levels.ron", &mut report);
+        assert!(!catalogs.is_empty());
+        for catalog_path in catalogs {
+            let catalog = read_ron::<LevelCatalog>(&catalog_path, &mut report).unwrap();
+            for level in catalog.levels {
+                let waves_path = assets_folder.join(&level.waves);
+                let waves = read_ron::<WavesDefinition>(&waves_path, &mut report).unwrap();
+                check_level_nodes(
+                    &assets_folder.join(&level.world),
+                    &waves,
+                    &waves_path,
+                    &mut report,
+                );
+            }
+        }
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn missing_spawn_points_and_paths_are_errors() {
+        let assets_folder = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let mut report = ValidationReport::default();
+        let waves: WavesDefinition = ron::from_str(
+            r#"(waves: [(enemy: "Fox", count: 1, interval: 1.0, spawn_point: "Nowhere",
+                path: "NoPath", delay: 0.0, speed: 1.0, health: 10.0)])"#,
+        )
+        .unwrap();
+        check_level_nodes(
+            &assets_folder.join("models/World.glb"),
+            &waves,
+            Path::new("test.waves.ron"),
+            &mut report,
+        );
+        assert_eq!(report.errors.len(), 2, "{:?}", report.errors);
+    }
+}